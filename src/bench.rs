@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use rrr::{
+    record::{RecordKey, RecordName, SuccessionNonce},
+    utils::fd_lock::FileLock,
+};
+
+use crate::{record::OwnedRecord, registry::OwnedRegistry};
+
+/// The result of measuring how fast record keys are hashed with a registry's parameters.
+#[derive(Clone, Debug)]
+pub struct HashingBenchmark {
+    /// The number of record keys hashed, each followed by a derivation of a succession nonce.
+    pub keys_hashed: usize,
+    pub elapsed: Duration,
+}
+
+impl HashingBenchmark {
+    pub fn keys_per_second(&self) -> f64 {
+        self.keys_hashed as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Hashes the keys of up to `sample_size` records of the tree of `root_record`, in the same way
+    /// as they are hashed when building the registry, without writing any records.
+    pub async fn run<L: FileLock>(
+        registry: &OwnedRegistry<L>,
+        root_record: &OwnedRecord,
+        sample_size: usize,
+    ) -> Result<Self> {
+        let root_predecessor_nonce = registry.kdf.get_root_record_predecessor_nonce().clone();
+        let mut records = vec![(root_record, root_predecessor_nonce)];
+        let mut keys_hashed = 0;
+        let start = Instant::now();
+
+        while keys_hashed < sample_size {
+            let Some((record, predecessor_nonce)) = records.pop() else {
+                break;
+            };
+            let key = RecordKey {
+                record_name: RecordName::from(record.config.name.to_vec()),
+                predecessor_nonce,
+            };
+            let hashed_key = key.hash(&registry.hash).await?;
+            let succession_nonce: SuccessionNonce =
+                hashed_key.derive_succession_nonce(&registry.kdf).await?;
+
+            keys_hashed += 1;
+            records.extend(
+                record
+                    .successive_records
+                    .iter()
+                    .map(|successive_record| (successive_record, succession_nonce.clone())),
+            );
+        }
+
+        Ok(Self {
+            keys_hashed,
+            elapsed: start.elapsed(),
+        })
+    }
+}
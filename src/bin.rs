@@ -1,6 +1,6 @@
 use clap::Parser;
 use color_eyre::eyre::Result;
-use rrr_make::cmd::Command;
+use rrr_make::cmd::{render_concise_error, Cli};
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
@@ -21,8 +21,33 @@ async fn setup_tracing() -> Result<()> {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if cli.global.verbose_errors {
+        color_eyre::install()?;
+    }
+
+    // In quiet mode, the exit code is the only output.
+    if cli.global.quiet {
+        if cli.command.process(&cli.global).await.is_err() {
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
     setup_tracing().await?;
-    Command::parse().process().await?;
+
+    if let Err(error) = cli.command.process(&cli.global).await {
+        if !cli.global.verbose_errors {
+            if let Some(concise_error) = render_concise_error(&error) {
+                eprintln!("{concise_error}");
+                std::process::exit(1);
+            }
+        }
+
+        return Err(error);
+    }
 
     Ok(())
 }
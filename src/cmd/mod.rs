@@ -1,16 +1,97 @@
-use std::path::PathBuf;
+use std::{
+    io::{IsTerminal, Write},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
-use crate::{make_recursive, registry::OwnedRegistry, MakeRecursiveStatistics};
-use clap::Parser;
-use color_eyre::eyre::Result;
+use crate::{
+    bench::HashingBenchmark,
+    config_diff::{diff_record_configs, RecordConfigChange},
+    doctor::{CheckStatus, DoctorReport},
+    error::Error,
+    graph::RecordGraph,
+    make_recursive,
+    name_transform::{LowercaseNameTransform, SlugifyNameTransform},
+    record::{OwnedRecord, OwnedRecordConfigUnresolved, OwnedRecordLoadOptions},
+    registry::{OwnedRegistry, OwnedRegistryLoadOptions},
+    spec::RecordSpec,
+    stats::RegistryStatistics,
+    user_config::UserConfig,
+    util::serde::ExplicitOption,
+    MakeRecursiveOptions, MakeRecursiveStatistics, RecordChange,
+};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{OptionExt, Report, Result};
 use rrr::{
-    registry::{Registry, RegistryConfig},
-    utils::fd_lock::WriteLock,
+    record::{Record, RecordName},
+    registry::RegistryConfig,
+    utils::fd_lock::{ReadLock, WriteLock},
 };
-use tracing::info;
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(version, about)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Arguments accepted by every subcommand.
+#[derive(Args, Default)]
+pub struct GlobalArgs {
+    /// Print the full chain of causes and the span trace of errors.
+    #[arg(long, global = true, default_value = "false")]
+    pub verbose_errors: bool,
+    /// Proceed with destructive operations without asking for confirmation.
+    #[arg(short = 'y', long, global = true, default_value = "false")]
+    pub assume_yes: bool,
+    /// Suppress all output, communicating solely through the exit code. Implies that destructive
+    /// operations are not confirmed interactively.
+    #[arg(short, long, global = true, default_value = "false")]
+    pub quiet: bool,
+}
+
+/// Receives the output of commands, so that it can be suppressed with `--quiet`.
+pub struct Reporter {
+    quiet: bool,
+    output: Box<dyn Write + Send>,
+}
+
+impl Reporter {
+    /// Creates a reporter writing to `output`, unless `quiet`.
+    pub fn new(quiet: bool, output: impl Write + Send + 'static) -> Self {
+        let output: Box<dyn Write + Send> = if quiet {
+            Box::new(std::io::sink())
+        } else {
+            Box::new(output)
+        };
+
+        Self { quiet, output }
+    }
+
+    pub fn stdout(quiet: bool) -> Self {
+        Self::new(quiet, std::io::stdout())
+    }
+
+    /// The output of the command, which is discarded in quiet mode.
+    pub fn output(&mut self) -> &mut dyn Write {
+        &mut self.output
+    }
+
+    /// Logs the summary of the command, unless in quiet mode.
+    pub fn summary(&self, summary: std::fmt::Arguments<'_>) {
+        if !self.quiet {
+            info!("{summary}");
+        }
+    }
+}
+
+#[derive(Subcommand)]
 pub enum Command {
     /// Creates a new source directory.
     New {
@@ -21,73 +102,931 @@ pub enum Command {
         force: bool,
     },
     /// Compiles a source directory into an RRR registry.
-    Make {
+    Make(MakeArgs),
+    /// Prints the resolved config and data files of a single record in a source directory.
+    DumpRecord {
         /// Path to a source directory.
         #[arg(short, long, default_value = ".")]
         input_directory: PathBuf,
-        /// Force existing files to be overwritten.
-        #[arg(short, long, default_value = "false")]
-        force: bool,
-        /// Whether a new revision should be created in the published directory.
+        /// Slash-separated sequence of record names leading from the root record to the record.
+        #[arg(default_value = "")]
+        path: String,
+        /// Also read the latest built version of the record from the output registry.
+        #[arg(long, default_value = "false")]
+        read: bool,
+    },
+    /// Prints the data files of a single record in a source directory, in the order in which they
+    /// are concatenated, along with the segment lengths the data is split into.
+    DataFiles {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+        /// Slash-separated sequence of record names leading from the root record to the record.
+        #[arg(default_value = "")]
+        path: String,
+    },
+    /// Reports the records whose resolved config differs from that of the same record in another
+    /// source directory.
+    ConfigDiff {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+        /// Path to the source directory to compare against, e.g. a previous snapshot.
+        against: PathBuf,
+    },
+    /// Measures how fast record keys are hashed with the hashing parameters of a source directory,
+    /// without building any records.
+    Bench {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+        /// The maximum number of record keys to hash, at least 1.
+        #[arg(
+            long,
+            default_value = "16",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        sample_size: usize,
+    },
+    /// Prints the default record parameters of a source directory, as records without parameters
+    /// of their own would use them.
+    Defaults {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+    },
+    /// Reports suspicious, but valid, configurations of the records in a source directory.
+    Lint {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+    },
+    /// Reports the number of records and the size of a source directory and its output registry.
+    Stats {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+        /// The format of the report.
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormat,
+    },
+    /// Prints the hierarchy of records in a source directory as a graph document.
+    Graph {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+        /// The format of the graph document.
+        #[arg(long, value_enum, default_value = "dot")]
+        format: GraphFormat,
+    },
+    /// Replaces the root predecessor nonce of a source directory with a freshly generated one.
+    /// Every record of the registry then has to be rebuilt, as all of their keys change.
+    RotateRootNonce {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+        /// Acknowledge that the previously built and published registry is invalidated.
         #[arg(long, default_value = "false")]
-        publish: bool,
+        i_know_this_invalidates_everything: bool,
     },
+    /// Runs non-destructive health checks on a source directory, reporting whether each passed,
+    /// warrants a warning, or failed. Fails if any check failed.
+    Doctor {
+        /// Path to a source directory.
+        #[arg(short, long, default_value = ".")]
+        input_directory: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum GraphFormat {
+    /// A Graphviz DOT document.
+    #[default]
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Args, Default)]
+pub struct MakeArgs {
+    /// Path to a source directory.
+    #[arg(short, long, default_value = ".")]
+    pub input_directory: PathBuf,
+    /// Force existing files to be overwritten.
+    #[arg(short, long, default_value = "false")]
+    pub force: bool,
+    /// Whether a new revision should be created in the published directory.
+    #[arg(long, default_value = "false")]
+    pub publish: bool,
+    /// Follow symbolic links to directories when looking for successive records.
+    #[arg(long, default_value = "false")]
+    pub follow_symlinks: bool,
+    /// Unix timestamp used as the creation time of records without a config file, instead of the
+    /// directory creation time, for reproducible builds.
+    #[arg(long, env = "SOURCE_DATE_EPOCH", value_parser = parse_unix_timestamp)]
+    pub source_date_epoch: Option<DateTime<Utc>>,
+    /// Path to a config of the output registry to use instead of the one derived from the source
+    /// registry. It must use the same hashing and key derivation, and include the verifying keys of
+    /// the source registry.
+    #[arg(long)]
+    pub output_registry_config: Option<PathBuf>,
+    /// Treat suspicious record configurations as errors rather than warnings.
+    #[arg(long, default_value = "false")]
+    pub strict: bool,
+    /// Only log the summary of the build, rather than every created or updated record.
+    #[arg(long, default_value = "false")]
+    pub summary_only: bool,
+    /// Abort the build if it takes longer than this many seconds. Records built before the time
+    /// limit is exceeded are kept.
+    #[arg(long)]
+    pub time_limit: Option<u64>,
+    /// Build into a copy of the staging directory, which replaces the staging directory only if
+    /// the build succeeds.
+    #[arg(long, default_value = "false")]
+    pub atomic_staging: bool,
+    /// Require every record to specify its parameters, rather than using the default record
+    /// parameters of the registry config.
+    #[arg(long, default_value = "false")]
+    pub no_default_parameters: bool,
+    /// Read a JSON record spec from the standard input, and build its records in place of the
+    /// records in the root record directory of the input registry. The registry config is still
+    /// read from the input registry, and record data in the spec must be UTF-8.
+    #[arg(long, default_value = "false")]
+    pub stdin: bool,
+    /// Build records marked as drafts, instead of skipping them.
+    #[arg(long, default_value = "false")]
+    pub include_drafts: bool,
+    /// Transformation applied to the name of each record except for the root record.
+    #[arg(long, value_enum, default_value = "none")]
+    pub name_transform: NameTransform,
+    /// Perform a dry run, and print every record which would be processed in processing order,
+    /// along with its predecessor nonce and its change in the output registry.
+    #[arg(long, default_value = "false")]
+    pub print_plan: bool,
+    /// Build into a discarded copy of the staging directory, leaving the staging directory
+    /// untouched.
+    #[arg(long, default_value = "false")]
+    pub dry_run: bool,
+    /// Perform a dry run, and print every record which would be created or updated in the staging
+    /// directory.
+    #[arg(long, default_value = "false")]
+    pub simulate: bool,
+    /// Also list unchanged records in the output of `--simulate`.
+    #[arg(long, default_value = "false", requires = "simulate")]
+    pub report_unchanged: bool,
+    /// Strip leading and trailing whitespace and trailing dots from record names derived from
+    /// directory names, and normalize them to Unicode NFC, so that the same source directory
+    /// builds to the same registry on every platform.
+    #[arg(long, default_value = "false")]
+    pub canonical_paths: bool,
+    /// Only warn about indexed data files of a record with different extensions, such as
+    /// `data.0.bin` and `data.1.txt`, rather than failing.
+    #[arg(long, default_value = "false")]
+    pub lenient_data_extensions: bool,
+    /// Fail if the registry config, a record config or a record manifest contains a key which is
+    /// not recognized, such as a misspelled parameter, rather than ignoring it.
+    #[arg(long, default_value = "false")]
+    pub treat_unknown_config_keys_as_errors: bool,
+    /// Fail if the data of a record marked as text is not valid UTF-8.
+    #[arg(long, default_value = "false")]
+    pub validate_utf8_data: bool,
+    /// Log the type and fingerprint of each signing key before building.
+    #[arg(long, default_value = "false")]
+    pub check_keys: bool,
+    /// Use the default record parameters of the user config, `rrr-make/config.toml` in the user's
+    /// config directory, for parameters which neither a record nor the registry config specifies.
+    #[arg(long, default_value = "false")]
+    pub use_user_config: bool,
+    /// Unix timestamp before which records are assumed to be unchanged, if neither their config
+    /// nor their data files were modified after it. Deleted records and changed registry defaults
+    /// are not detected.
+    #[arg(long, value_parser = parse_unix_timestamp)]
+    pub since_mtime: Option<DateTime<Utc>>,
+    /// Additional directory to replace with a copy of the staging directory after a successful
+    /// build. Can be repeated.
+    #[arg(long)]
+    pub extra_staging: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum NameTransform {
+    /// Use record names verbatim.
+    #[default]
+    None,
+    /// Convert record names to lowercase.
+    Lowercase,
+    /// Convert record names to lowercase, separating words with `-`.
+    Slugify,
+}
+
+fn parse_unix_timestamp(string: &str) -> Result<DateTime<Utc>, String> {
+    let seconds = string.parse::<i64>().map_err(|error| error.to_string())?;
+
+    DateTime::from_timestamp(seconds, 0).ok_or_else(|| format!("timestamp out of range: {seconds}"))
+}
+
+impl MakeArgs {
+    pub fn load_options(&self) -> OwnedRecordLoadOptions {
+        OwnedRecordLoadOptions {
+            follow_symlinks: self.follow_symlinks,
+            source_date_epoch: self.source_date_epoch,
+            no_default_parameters: self.no_default_parameters,
+            canonical_paths: self.canonical_paths,
+            user_default_record_parameters: None,
+            deny_inconsistent_data_extensions: !self.lenient_data_extensions,
+            deny_unknown_config_keys: self.treat_unknown_config_keys_as_errors,
+        }
+    }
+
+    /// Whether the build should leave the staging directory untouched.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run || self.simulate || self.print_plan
+    }
+
+    pub fn make_options(&self) -> MakeRecursiveOptions {
+        MakeRecursiveOptions {
+            strict: self.strict,
+            summary_only: self.summary_only,
+            deadline: self
+                .time_limit
+                .map(|time_limit| Instant::now() + Duration::from_secs(time_limit)),
+            include_drafts: self.include_drafts,
+            name_transform: match self.name_transform {
+                NameTransform::None => None,
+                NameTransform::Lowercase => Some(Arc::new(LowercaseNameTransform)),
+                NameTransform::Slugify => Some(Arc::new(SlugifyNameTransform)),
+            },
+            validate_utf8_data: self.validate_utf8_data,
+            since_mtime: self.since_mtime.map(SystemTime::from),
+        }
+    }
+}
+
+/// Renders errors known to this crate as a single line, consisting of the outermost context
+/// followed by the error of this crate at the root of the chain, skipping any causes in between.
+/// Returns `None` for other errors, which are best reported in full.
+pub fn render_concise_error(error: &Report) -> Option<String> {
+    let root = error.downcast_ref::<Error>()?.to_string();
+    let outermost = error.to_string();
+
+    Some(if outermost == root {
+        format!("Error: {root}")
+    } else {
+        format!("Error: {outermost}: {root}")
+    })
+}
+
+/// Asks the user to confirm a destructive `operation`, failing if the confirmation is not given.
+/// With `assume_yes`, the operation is confirmed without asking. When not `interactive`, the
+/// operation is refused without asking, as there is nobody to answer.
+pub fn confirm(operation: &str, assume_yes: bool, interactive: bool) -> Result<()> {
+    if assume_yes {
+        return Ok(());
+    }
+
+    if !interactive {
+        return Err(Error::ConfirmationRequired {
+            operation: operation.to_owned(),
+        }
+        .into());
+    }
+
+    eprint!("About to {operation}. Proceed? [y/N] ");
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+
+    std::io::stdin().read_line(&mut answer)?;
+
+    if matches!(answer.trim(), "y" | "Y" | "yes") {
+        Ok(())
+    } else {
+        Err(Error::ConfirmationDeclined {
+            operation: operation.to_owned(),
+        }
+        .into())
+    }
+}
+
+/// Finds the record at the slash-separated `path` of record names, relative to the root record.
+/// Returns the record along with the names of all records along the path, including the root record.
+fn find_record<'a>(
+    root_record: &'a OwnedRecord,
+    path: &str,
+) -> Result<(&'a OwnedRecord, Vec<RecordName>)> {
+    let mut record = root_record;
+    let mut record_names = vec![RecordName::from(root_record.config.name.to_vec())];
+
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        record = record
+            .successive_records
+            .iter()
+            .find(|successive_record| successive_record.config.name.as_slice() == name.as_bytes())
+            .ok_or_else(|| Error::RecordNotFound {
+                path: path.to_owned(),
+            })?;
+        record_names.push(RecordName::from(record.config.name.to_vec()));
+    }
+
+    Ok((record, record_names))
 }
 
 impl Command {
-    pub async fn process(self) -> Result<()> {
+    pub async fn process(self, global: &GlobalArgs) -> Result<()> {
+        self.process_with_reporter(global, &mut Reporter::stdout(global.quiet))
+            .await
+    }
+
+    pub async fn process_with_reporter(
+        self,
+        global: &GlobalArgs,
+        reporter: &mut Reporter,
+    ) -> Result<()> {
         match self {
             Command::New { directory, force } => {
-                OwnedRegistry::generate(&directory, force).await.unwrap();
-                println!("New registry successfully generated in {directory:?}.");
+                let directory_has_entries = match tokio::fs::read_dir(&directory).await {
+                    Ok(mut entries) => entries.next_entry().await?.is_some(),
+                    // Errors are reported by `OwnedRegistry::generate`.
+                    Err(_) => false,
+                };
+
+                if force && directory_has_entries {
+                    confirm(
+                        &format!("overwrite existing files in {directory:?}"),
+                        global.assume_yes,
+                        !global.quiet && std::io::stdin().is_terminal(),
+                    )?;
+                }
+
+                OwnedRegistry::generate(&directory, force).await?;
+                writeln!(
+                    reporter.output(),
+                    "New registry successfully generated in {directory:?}."
+                )?;
             }
-            Command::Make {
-                input_directory,
-                force,
-                publish,
-            } => {
-                let input_registry = OwnedRegistry::<WriteLock>::load(input_directory).await?;
-                let input_root_record = input_registry.load_root_record().await?;
-                let mut output_registry = Registry::create(
-                    input_registry.get_staging_directory_path(),
-                    RegistryConfig::from(&input_registry),
-                    force,
+            Command::Make(args) => {
+                let make_options = args.make_options();
+
+                let input_registry = OwnedRegistry::<WriteLock>::load_with_options(
+                    &args.input_directory,
+                    &OwnedRegistryLoadOptions {
+                        deny_unknown_config_keys: args.treat_unknown_config_keys_as_errors,
+                        ..Default::default()
+                    },
                 )
                 .await?;
-                let root_predecessor_nonce = output_registry
-                    .config
-                    .kdf
-                    .get_root_record_predecessor_nonce()
-                    .clone();
 
-                // TODO: Verify target registry keys
+                input_registry.ensure_output_disjoint_from_source()?;
+
+                // Dry runs leave the staging area untouched, including interrupted swaps.
+                if !args.is_dry_run() {
+                    input_registry.recover_staging().await?;
+
+                    for extra_staging_directory_path in &args.extra_staging {
+                        input_registry
+                            .ensure_extra_staging_disjoint(extra_staging_directory_path)?;
+
+                        if !OwnedRegistry::<WriteLock>::is_replaceable_extra_staging(
+                            extra_staging_directory_path,
+                        )
+                        .await?
+                        {
+                            confirm(
+                                &format!("replace {extra_staging_directory_path:?}, which is not an output registry"),
+                                global.assume_yes,
+                                !global.quiet && std::io::stdin().is_terminal(),
+                            )?;
+                        }
+                    }
+                }
+
+                if let Some(since_mtime) = args.since_mtime {
+                    warn!(
+                        %since_mtime,
+                        "Only rebuilding records modified since the given time. Run a full build to account for deleted records and changed registry defaults."
+                    );
+                }
+
+                if args.check_keys {
+                    // Malformed keys already fail loading the registry.
+                    for (key_type, fingerprint) in input_registry
+                        .signing_key_types()
+                        .into_iter()
+                        .zip(input_registry.verifying_key_fingerprints())
+                    {
+                        info!(key_type, fingerprint, "Signing key loaded.");
+                    }
+                }
+
+                let mut load_options = args.load_options();
+
+                if args.use_user_config {
+                    let user_config_path = UserConfig::default_path()
+                        .ok_or_eyre("Could not determine the location of the user config.")?;
+                    let user_config = UserConfig::load(user_config_path).await?;
+
+                    load_options.user_default_record_parameters =
+                        Some(user_config.default_record_parameters);
+                }
+
+                // Holds the records of the spec read from the standard input until the build is
+                // done, as their data is read from it.
+                let spec_directory = if args.stdin {
+                    let mut spec_string = String::new();
+
+                    tokio::io::stdin().read_to_string(&mut spec_string).await?;
+
+                    let spec = serde_json::from_str::<RecordSpec>(&spec_string)?;
+                    let spec_directory = tempfile::tempdir()?;
+
+                    spec.materialize(spec_directory.path()).await?;
+                    Some(spec_directory)
+                } else {
+                    None
+                };
+                let input_root_record = match spec_directory.as_ref() {
+                    Some(spec_directory) => {
+                        input_registry
+                            .load_root_record_from(spec_directory.path(), &load_options)
+                            .await?
+                    }
+                    None => {
+                        input_registry
+                            .load_root_record_with_options(&load_options)
+                            .await?
+                    }
+                };
+
+                if !input_registry.allow_empty && input_root_record.is_empty().await? {
+                    return Err(Error::EmptyRegistry {
+                        directory: input_root_record.directory_path.clone(),
+                    }
+                    .into());
+                }
+
+                let output_registry_config = match args.output_registry_config.as_ref() {
+                    Some(output_registry_config_path) => {
+                        let output_registry_config_string =
+                            tokio::fs::read_to_string(output_registry_config_path).await?;
+
+                        toml::from_str::<RegistryConfig>(&output_registry_config_string)?
+                    }
+                    None => RegistryConfig::from(&input_registry),
+                };
+                // Dry runs build into a copy of the staging directory, which is removed along with
+                // this temporary directory.
+                let scratch_directory = if args.is_dry_run() {
+                    Some(tempfile::tempdir()?)
+                } else {
+                    None
+                };
+                let output_directory_path = match scratch_directory.as_ref() {
+                    Some(scratch_directory) => {
+                        let output_directory_path = scratch_directory.path().join("staging");
+
+                        input_registry
+                            .copy_staging_to(&output_directory_path)
+                            .await?;
+                        output_directory_path
+                    }
+                    None if args.atomic_staging => input_registry.prepare_pending_staging().await?,
+                    None => input_registry.get_staging_directory_path(),
+                };
                 let mut stats = MakeRecursiveStatistics::default();
+                let make_result = async {
+                    let mut output_registry = input_registry
+                        .create_output_registry_at(
+                            &output_directory_path,
+                            output_registry_config,
+                            args.force,
+                        )
+                        .await?;
+                    let root_predecessor_nonce = output_registry
+                        .config
+                        .kdf
+                        .get_root_record_predecessor_nonce()
+                        .clone();
 
-                make_recursive(
-                    &mut output_registry,
-                    &input_registry,
-                    &input_root_record,
-                    &root_predecessor_nonce,
-                    0, // TODO
-                    0, // TODO
-                    &mut Vec::new(),
-                    &mut stats,
-                )
-                .await?;
+                    // TODO: Verify target registry keys
+                    make_recursive(
+                        &mut output_registry,
+                        &input_registry,
+                        &input_root_record,
+                        &root_predecessor_nonce,
+                        0, // TODO
+                        0, // TODO
+                        &make_options,
+                        &mut Vec::new(),
+                        &mut stats,
+                    )
+                    .await
+                }
+                .await;
+
+                let staging_result = if args.atomic_staging && !args.is_dry_run() {
+                    match make_result {
+                        Ok(()) => input_registry.commit_pending_staging().await,
+                        Err(_) => input_registry.discard_pending_staging().await,
+                    }
+                } else {
+                    Ok(())
+                };
+
+                // A failed build is the cause of any failure to clean up after it.
+                make_result?;
+                staging_result?;
+
+                if !args.is_dry_run() {
+                    for extra_staging_directory_path in &args.extra_staging {
+                        input_registry
+                            .replicate_staging(extra_staging_directory_path)
+                            .await?;
+                    }
+                }
+
+                if args.print_plan {
+                    for (index, processed_record) in stats.processed_records.iter().enumerate() {
+                        writeln!(
+                            reporter.output(),
+                            "{index}: {} {:?} (predecessor nonce {:?})",
+                            processed_record.record_path,
+                            processed_record.change,
+                            processed_record.predecessor_nonce,
+                        )?;
+                    }
+                }
+
+                if args.simulate {
+                    for processed_record in &stats.processed_records {
+                        let reported = match processed_record.change {
+                            RecordChange::Created | RecordChange::Updated => true,
+                            RecordChange::Unchanged => args.report_unchanged,
+                            RecordChange::Skipped => false,
+                        };
 
-                if stats.records_created == 0 && stats.records_updated == 0 {
-                    info! {
-                        "Target registry unchanged. Checked {} records in total.",
+                        if reported {
+                            writeln!(
+                                reporter.output(),
+                                "{:?}: {}",
+                                processed_record.change,
+                                processed_record.record_path,
+                            )?;
+                        }
+                    }
+                }
+
+                if args.is_dry_run() {
+                    reporter.summary(format_args! {
+                        "Dry run, target registry left untouched. {} records would be created, {} updated, {} unchanged, {} records skipped.",
+                        stats.records_created,
+                        stats.records_updated,
+                        stats.records_unchanged,
+                        stats.records_skipped,
+                    });
+                } else if stats.records_created == 0 && stats.records_updated == 0 {
+                    reporter.summary(format_args! {
+                        "Target registry unchanged. Checked {} records in total, {} records skipped.",
                         stats.records_created + stats.records_updated + stats.records_unchanged,
-                    };
+                        stats.records_skipped,
+                    });
                 } else {
-                    info! {
-                        "Target registry updated. Checked {} records in total. {} new records created, {} existing records updated, {} existing records unchanged.",
+                    reporter.summary(format_args! {
+                        "Target registry updated. Checked {} records in total. {} new records created, {} existing records updated, {} existing records unchanged, {} records skipped.",
                         stats.records_created + stats.records_updated + stats.records_unchanged,
                         stats.records_created,
                         stats.records_updated,
                         stats.records_unchanged,
-                    };
+                        stats.records_skipped,
+                    });
+                }
+            }
+            Command::DumpRecord {
+                input_directory,
+                path,
+                read,
+            } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let (record, record_names) = find_record(&input_root_record, &path)?;
+                let config_string = toml::to_string_pretty(&OwnedRecordConfigUnresolved::from(
+                    record.config.clone(),
+                ))?;
+
+                writeln!(
+                    reporter.output(),
+                    "Record directory: {:?}",
+                    record.directory_path
+                )?;
+                writeln!(reporter.output(), "Resolved config:\n{config_string}")?;
+                writeln!(reporter.output(), "Data files:")?;
+
+                for data_path in record.get_data_paths().await? {
+                    let data_length = tokio::fs::metadata(&data_path).await?.len();
+                    writeln!(reporter.output(), "  {data_path:?} ({data_length} bytes)")?;
+                }
+
+                if read {
+                    let mut output_registry = input_registry.open_output_registry().await?;
+                    let hashed_key = input_registry.hash_record_path(&record_names).await?;
+                    let versions = output_registry
+                        .list_record_versions(&hashed_key, 0, 0)
+                        .await?;
+                    let latest_version = versions
+                        .last()
+                        .ok_or_eyre("The record is not present in the output registry.")?;
+                    let output_record = Record::read_version_with_nonce(
+                        &mut output_registry,
+                        &hashed_key,
+                        latest_version.record_version,
+                        latest_version.record_nonce,
+                    )
+                    .await?
+                    .ok_or_eyre("Failed to load the latest version of the record.")?;
+
+                    writeln!(
+                        reporter.output(),
+                        "Latest version: {}",
+                        latest_version.record_version.0
+                    )?;
+                    writeln!(
+                        reporter.output(),
+                        "Data length: {} bytes",
+                        output_record.record.data.0.len()
+                    )?;
+                    writeln!(
+                        reporter.output(),
+                        "Metadata: {:?}",
+                        output_record.record.metadata
+                    )?;
+                }
+            }
+            Command::DataFiles {
+                input_directory,
+                path,
+            } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let (record, _) = find_record(&input_root_record, &path)?;
+                let data_paths = record.get_data_paths().await?;
+                let indices = data_paths
+                    .iter()
+                    .map(|data_path| {
+                        data_path
+                            .file_name()
+                            .and_then(OwnedRecord::parse_data_file_name)
+                            .flatten()
+                    })
+                    .collect::<Vec<_>>();
+                let classification = if record.data_ref_path.is_some() {
+                    "referenced by `data_ref`"
+                } else if indices.first().is_some_and(Option::is_some) {
+                    "indexed"
+                } else {
+                    "non-indexed"
+                };
+
+                writeln!(reporter.output(), "Data files ({classification}):")?;
+
+                for (data_path, index) in data_paths.iter().zip(indices) {
+                    let data_length = tokio::fs::metadata(data_path).await?.len();
+
+                    match index {
+                        Some(index) => writeln!(
+                            reporter.output(),
+                            "  {index}: {data_path:?} ({data_length} bytes)"
+                        )?,
+                        None => {
+                            writeln!(reporter.output(), "  {data_path:?} ({data_length} bytes)")?
+                        }
+                    }
+                }
+
+                let split_at = record
+                    .read()
+                    .await?
+                    .and_then(|read_success| read_success.split_at);
+
+                writeln!(reporter.output(), "Split at: {split_at:?}")?;
+            }
+            Command::ConfigDiff {
+                input_directory,
+                against,
+            } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let against_registry = OwnedRegistry::<ReadLock>::load(against).await?;
+                let against_root_record = against_registry.load_root_record().await?;
+                let diffs = diff_record_configs(&input_root_record, &against_root_record);
+
+                for diff in &diffs {
+                    match &diff.change {
+                        RecordConfigChange::Added => {
+                            writeln!(reporter.output(), "+ {}", diff.record_path)?
+                        }
+                        RecordConfigChange::Removed => {
+                            writeln!(reporter.output(), "- {}", diff.record_path)?
+                        }
+                        RecordConfigChange::Changed { fields } => writeln!(
+                            reporter.output(),
+                            "~ {} ({})",
+                            diff.record_path,
+                            fields.join(", ")
+                        )?,
+                    }
+                }
+
+                reporter.summary(format_args!(
+                    "{} records with differing configs.",
+                    diffs.len()
+                ));
+            }
+            Command::Bench {
+                input_directory,
+                sample_size,
+            } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let benchmark =
+                    HashingBenchmark::run(&input_registry, &input_root_record, sample_size).await?;
+
+                writeln!(
+                    reporter.output(),
+                    "Hashed {} record keys in {:.3} s, {:.3} keys/s.",
+                    benchmark.keys_hashed,
+                    benchmark.elapsed.as_secs_f64(),
+                    benchmark.keys_per_second()
+                )?;
+            }
+            Command::Defaults { input_directory } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let defaults = &input_registry.config.default_record_parameters;
+                let unspecified = "unspecified, must be specified by each record";
+
+                match defaults.splitting_strategy.as_ref() {
+                    Some(splitting_strategy) => writeln!(
+                        reporter.output(),
+                        "Splitting strategy: {splitting_strategy:?}"
+                    )?,
+                    None => writeln!(reporter.output(), "Splitting strategy: {unspecified}")?,
+                }
+
+                match defaults.encryption.as_ref() {
+                    Some(ExplicitOption::Some(encryption)) => {
+                        let render =
+                            |value: Option<String>| value.unwrap_or_else(|| unspecified.to_owned());
+
+                        writeln!(reporter.output(), "Encryption:")?;
+                        writeln!(
+                            reporter.output(),
+                            "  Algorithm: {}",
+                            render(
+                                encryption
+                                    .algorithm
+                                    .map(|algorithm| format!("{algorithm:?}"))
+                            )
+                        )?;
+                        writeln!(
+                            reporter.output(),
+                            "  Segment padding: {}",
+                            render(
+                                encryption
+                                    .segment_padding_to_bytes
+                                    .map(|padding| format!("{padding} bytes"))
+                            )
+                        )?;
+                    }
+                    Some(ExplicitOption::None(_)) => {
+                        writeln!(reporter.output(), "Encryption: none (explicitly disabled)")?
+                    }
+                    None => writeln!(reporter.output(), "Encryption: {unspecified}")?,
+                }
+            }
+            Command::Lint { input_directory } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let mut lint_count = 0;
+
+                for (_, record) in input_root_record.iter() {
+                    for lint in record
+                        .lint(std::ptr::eq(record, &input_root_record))
+                        .await?
+                    {
+                        writeln!(
+                            reporter.output(),
+                            "{}: {:?}: {}",
+                            lint.severity,
+                            record.directory_path,
+                            lint.message
+                        )?;
+                        lint_count += 1;
+                    }
+                }
+
+                reporter.summary(format_args!("{lint_count} lints reported."));
+            }
+            Command::Stats {
+                input_directory,
+                output,
+            } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let stats =
+                    RegistryStatistics::collect(&input_registry, &input_root_record).await?;
+
+                match output {
+                    OutputFormat::Text => {
+                        writeln!(
+                            reporter.output(),
+                            "Source records: {}",
+                            stats.source_records
+                        )?;
+
+                        for (encryption, records) in &stats.source_records_by_encryption {
+                            writeln!(
+                                reporter.output(),
+                                "Source records with encryption {encryption}: {records}"
+                            )?;
+                        }
+
+                        writeln!(
+                            reporter.output(),
+                            "Source data: {} bytes",
+                            stats.source_data_bytes
+                        )?;
+                        writeln!(
+                            reporter.output(),
+                            "Output registry: {} bytes",
+                            stats.output_registry_bytes
+                        )?;
+                    }
+                    OutputFormat::Json => {
+                        writeln!(
+                            reporter.output(),
+                            "{}",
+                            serde_json::to_string_pretty(&stats)?
+                        )?;
+                    }
+                }
+            }
+            Command::Graph {
+                input_directory,
+                format,
+            } => {
+                let input_registry = OwnedRegistry::<ReadLock>::load(input_directory).await?;
+                let input_root_record = input_registry.load_root_record().await?;
+                let graph = RecordGraph::from_root_record(&input_root_record);
+                let document = match format {
+                    GraphFormat::Dot => graph.to_dot(),
+                    GraphFormat::Mermaid => graph.to_mermaid(),
+                };
+
+                write!(reporter.output(), "{document}")?;
+            }
+            Command::RotateRootNonce {
+                input_directory,
+                i_know_this_invalidates_everything,
+            } => {
+                if !i_know_this_invalidates_everything {
+                    return Err(Error::RootNonceRotationNotAcknowledged.into());
+                }
+
+                let mut input_registry = OwnedRegistry::<WriteLock>::load(input_directory).await?;
+
+                input_registry.rotate_root_predecessor_nonce().await?;
+                warn!("The root predecessor nonce was rotated. Every record has to be rebuilt, and the published registry is invalidated.");
+            }
+            Command::Doctor { input_directory } => {
+                let report = DoctorReport::collect(input_directory).await;
+
+                for check in &report.checks {
+                    writeln!(
+                        reporter.output(),
+                        "{}: {}: {}",
+                        check.status,
+                        check.name,
+                        check.message
+                    )?;
+                }
+
+                reporter.summary(format_args!(
+                    "{} checks passed, {} warnings, {} failures.",
+                    report.count(CheckStatus::Pass),
+                    report.count(CheckStatus::Warn),
+                    report.count(CheckStatus::Fail),
+                ));
+
+                if report.has_failures() {
+                    return Err(Error::DoctorChecksFailed {
+                        failed: report.count(CheckStatus::Fail),
+                    }
+                    .into());
                 }
             }
         }
@@ -99,5 +1038,5 @@ impl Command {
 #[test]
 fn verify_cli() {
     use clap::CommandFactory;
-    Command::command().debug_assert();
+    Cli::command().debug_assert();
 }
@@ -0,0 +1,167 @@
+use rrr::record::{RecordName, RecordPath};
+use serde::Serialize;
+
+use crate::record::{OwnedRecord, OwnedRecordConfig, OwnedRecordConfigParameters};
+
+/// How the resolved config of a record differs between two source directories.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordConfigChange {
+    /// The record is only present in the current source directory.
+    Added,
+    /// The record is only present in the source directory compared against.
+    Removed,
+    /// The record is present in both source directories, but the listed fields of its resolved
+    /// config differ.
+    Changed { fields: Vec<&'static str> },
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct RecordConfigDiff {
+    pub record_path: String,
+    pub change: RecordConfigChange,
+}
+
+/// Compares the resolved configs of all records in the tree of `current_root_record` against the
+/// records with the same record path in the tree of `against_root_record`.
+/// Records whose configs are equal are omitted.
+pub fn diff_record_configs(
+    current_root_record: &OwnedRecord,
+    against_root_record: &OwnedRecord,
+) -> Vec<RecordConfigDiff> {
+    let mut diffs = Vec::new();
+
+    diff_record_configs_recursive(
+        Some(current_root_record),
+        Some(against_root_record),
+        &mut Vec::new(),
+        &mut diffs,
+    );
+
+    diffs
+}
+
+fn diff_record_configs_recursive(
+    current_record: Option<&OwnedRecord>,
+    against_record: Option<&OwnedRecord>,
+    path_to_parent_record: &mut Vec<RecordName>,
+    diffs: &mut Vec<RecordConfigDiff>,
+) {
+    let Some(record) = current_record.or(against_record) else {
+        return;
+    };
+    let change = match (current_record, against_record) {
+        (Some(current_record), Some(against_record)) => {
+            let fields = diff_config_fields(&current_record.config, &against_record.config);
+
+            (!fields.is_empty()).then_some(RecordConfigChange::Changed { fields })
+        }
+        (Some(_), None) => Some(RecordConfigChange::Added),
+        (None, _) => Some(RecordConfigChange::Removed),
+    };
+
+    path_to_parent_record.push(RecordName::from(record.config.name.to_vec()));
+
+    if let Some(change) = change {
+        diffs.push(RecordConfigDiff {
+            record_path: RecordPath::try_from(path_to_parent_record.clone())
+                .unwrap()
+                .to_string(),
+            change,
+        });
+    }
+
+    for successive_record in current_record
+        .iter()
+        .flat_map(|record| record.successive_records.iter())
+    {
+        diff_record_configs_recursive(
+            Some(successive_record),
+            find_successive_record(against_record, &successive_record.config.name),
+            path_to_parent_record,
+            diffs,
+        );
+    }
+
+    for successive_record in against_record
+        .iter()
+        .flat_map(|record| record.successive_records.iter())
+    {
+        if find_successive_record(current_record, &successive_record.config.name).is_none() {
+            diff_record_configs_recursive(
+                None,
+                Some(successive_record),
+                path_to_parent_record,
+                diffs,
+            );
+        }
+    }
+
+    path_to_parent_record.pop();
+}
+
+/// Lists the fields of the resolved configs which differ.
+fn diff_config_fields(
+    current_config: &OwnedRecordConfig,
+    against_config: &OwnedRecordConfig,
+) -> Vec<&'static str> {
+    // Destructured exhaustively, so that new fields cannot be left out of the comparison.
+    let OwnedRecordConfig {
+        // Records are compared against the record with the same name.
+        name: _,
+        data_ref,
+        intermediate,
+        draft,
+        text,
+        version_ignore_metadata,
+        metadata,
+        parameters:
+            OwnedRecordConfigParameters {
+                splitting_strategy,
+                encryption,
+                normalize_line_endings,
+            },
+    } = current_config;
+    let mut fields = Vec::new();
+    let mut compare = |name, changed: bool| {
+        if changed {
+            fields.push(name);
+        }
+    };
+
+    compare("data_ref", *data_ref != against_config.data_ref);
+    compare("intermediate", *intermediate != against_config.intermediate);
+    compare("draft", *draft != against_config.draft);
+    compare("text", *text != against_config.text);
+    compare(
+        "version_ignore_metadata",
+        *version_ignore_metadata != against_config.version_ignore_metadata,
+    );
+    compare("metadata", *metadata != against_config.metadata);
+    compare(
+        "splitting_strategy",
+        *splitting_strategy != against_config.parameters.splitting_strategy,
+    );
+    compare(
+        "encryption",
+        *encryption != against_config.parameters.encryption,
+    );
+    compare(
+        "normalize_line_endings",
+        *normalize_line_endings != against_config.parameters.normalize_line_endings,
+    );
+
+    fields
+}
+
+fn find_successive_record<'a>(
+    record: Option<&'a OwnedRecord>,
+    name: &[u8],
+) -> Option<&'a OwnedRecord> {
+    record.and_then(|record| {
+        record
+            .successive_records
+            .iter()
+            .find(|successive_record| successive_record.config.name.as_slice() == name)
+    })
+}
@@ -0,0 +1,403 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use color_eyre::Result;
+use rrr::utils::fd_lock::ReadLock;
+
+use crate::{
+    error::Error,
+    record::{OwnedRecord, OwnedRecordLoadOptions},
+    registry::{OwnedRegistry, OwnedRegistryLoadOptions},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Pass,
+    /// The source directory can be built, but likely not as intended.
+    Warn,
+    /// The source directory cannot be built.
+    Fail,
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pass => write!(f, "pass"),
+            Self::Warn => write!(f, "warn"),
+            Self::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// The outcome of a single health check of a source directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+/// The outcomes of the health checks of a source directory, in the order in which they were run.
+/// Checks which depend on a failed check are not run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// Runs all health checks on the source directory in `directory_path`, without modifying it.
+    pub async fn collect(directory_path: impl AsRef<Path>) -> Self {
+        let mut report = Self::default();
+
+        report.collect_checks(directory_path.as_ref()).await;
+        report
+    }
+
+    /// Counts the checks with the given `status`.
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(CheckStatus::Fail) > 0
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus, message: impl Into<String>) {
+        self.checks.push(Check {
+            name,
+            status,
+            message: message.into(),
+        });
+    }
+
+    async fn collect_checks(&mut self, directory_path: &Path) {
+        let Some(registry) = self.check_registry_config(directory_path).await else {
+            return;
+        };
+
+        self.check_signing_keys(&registry).await;
+
+        match registry.ensure_output_disjoint_from_source() {
+            Ok(()) => self.push(
+                "output paths",
+                CheckStatus::Pass,
+                "the output directories are separate from the root record directory",
+            ),
+            Err(error) => self.push("output paths", CheckStatus::Fail, error.to_string()),
+        }
+
+        let Some(root_record) = self.check_record_configs(&registry).await else {
+            return;
+        };
+
+        self.check_empty_registry(&registry, &root_record).await;
+        self.check_required_encryption(&registry, &root_record);
+        self.check_record_names(&root_record);
+
+        if let Err(error) = self.check_stray_files(&root_record).await {
+            self.push("stray files", CheckStatus::Fail, error.to_string());
+        }
+    }
+
+    async fn check_registry_config(
+        &mut self,
+        directory_path: &Path,
+    ) -> Option<OwnedRegistry<ReadLock>> {
+        let strict_options = OwnedRegistryLoadOptions {
+            deny_unknown_config_keys: true,
+            ..Default::default()
+        };
+        let error =
+            match OwnedRegistry::<ReadLock>::load_with_options(directory_path, &strict_options)
+                .await
+            {
+                Ok(registry) => {
+                    self.push("registry config", CheckStatus::Pass, "the config is valid");
+                    return Some(registry);
+                }
+                Err(error) => error,
+            };
+
+        if let Some(Error::UnknownConfigKeys { .. }) = error.downcast_ref::<Error>() {
+            self.push("registry config", CheckStatus::Warn, error.to_string());
+
+            return match OwnedRegistry::<ReadLock>::load(directory_path).await {
+                Ok(registry) => Some(registry),
+                Err(error) => {
+                    self.push_registry_load_failure(error);
+                    None
+                }
+            };
+        }
+
+        self.push_registry_load_failure(error);
+        None
+    }
+
+    fn push_registry_load_failure(&mut self, error: color_eyre::Report) {
+        // Signing keys are loaded along with the config.
+        let name = match error.downcast_ref::<Error>() {
+            Some(Error::MalformedSigningKey { .. }) => "signing keys",
+            _ => "registry config",
+        };
+
+        self.push(name, CheckStatus::Fail, error.to_string());
+    }
+
+    /// Warns about signing keys which other users can read. The registry config is only loaded if
+    /// it has at least one signing key.
+    async fn check_signing_keys(&mut self, registry: &OwnedRegistry<ReadLock>) {
+        let mut exposed_key_paths = Vec::new();
+
+        for key_path in registry.get_signing_key_paths() {
+            match tokio::fs::metadata(&key_path).await {
+                Ok(metadata) if is_accessible_to_others(&metadata) => {
+                    exposed_key_paths.push(key_path)
+                }
+                Ok(_) => (),
+                Err(error) => {
+                    self.push("signing keys", CheckStatus::Fail, error.to_string());
+                    return;
+                }
+            }
+        }
+
+        if exposed_key_paths.is_empty() {
+            self.push(
+                "signing keys",
+                CheckStatus::Pass,
+                format!("{} signing keys loaded", registry.signing_key_count()),
+            );
+        } else {
+            self.push(
+                "signing keys",
+                CheckStatus::Warn,
+                format!("signing keys are accessible to other users: {exposed_key_paths:?}"),
+            );
+        }
+    }
+
+    async fn check_record_configs(
+        &mut self,
+        registry: &OwnedRegistry<ReadLock>,
+    ) -> Option<OwnedRecord> {
+        let root_record_path = registry.get_root_record_path();
+
+        if !tokio::fs::try_exists(&root_record_path)
+            .await
+            .unwrap_or(false)
+        {
+            self.push(
+                "root record",
+                CheckStatus::Fail,
+                format!("the root record directory {root_record_path:?} does not exist"),
+            );
+            return None;
+        }
+
+        self.push(
+            "root record",
+            CheckStatus::Pass,
+            "the root record directory exists",
+        );
+
+        // Data extensions are checked as strictly as by `make`.
+        let options = OwnedRecordLoadOptions {
+            deny_inconsistent_data_extensions: true,
+            ..Default::default()
+        };
+        let strict_options = OwnedRecordLoadOptions {
+            deny_unknown_config_keys: true,
+            ..options.clone()
+        };
+        let error = match registry
+            .load_root_record_with_options(&strict_options)
+            .await
+        {
+            Ok(root_record) => {
+                self.push(
+                    "record configs",
+                    CheckStatus::Pass,
+                    format!("{} records loaded", root_record.count_records()),
+                );
+                return Some(root_record);
+            }
+            Err(error) => error,
+        };
+
+        if let Some(Error::UnknownConfigKeys { .. }) = error.downcast_ref::<Error>() {
+            self.push("record configs", CheckStatus::Warn, error.to_string());
+
+            return match registry.load_root_record_with_options(&options).await {
+                Ok(root_record) => Some(root_record),
+                Err(error) => {
+                    self.push("record configs", CheckStatus::Fail, error.to_string());
+                    None
+                }
+            };
+        }
+
+        // Includes duplicate record names and unresolved parameters.
+        self.push("record configs", CheckStatus::Fail, error.to_string());
+        None
+    }
+
+    /// Fails if the root record is empty, unless the registry config allows it, as `make` would.
+    async fn check_empty_registry(
+        &mut self,
+        registry: &OwnedRegistry<ReadLock>,
+        root_record: &OwnedRecord,
+    ) {
+        match root_record.is_empty().await {
+            Ok(true) if !registry.allow_empty => self.push(
+                "empty registry",
+                CheckStatus::Fail,
+                Error::EmptyRegistry {
+                    directory: root_record.directory_path.clone(),
+                }
+                .to_string(),
+            ),
+            Ok(_) => self.push(
+                "empty registry",
+                CheckStatus::Pass,
+                "the root record is not empty, or empty registries are allowed",
+            ),
+            Err(error) => self.push("empty registry", CheckStatus::Fail, error.to_string()),
+        }
+    }
+
+    /// Fails if records which the `require_encryption` policy requires to be encrypted are not.
+    /// Drafts are checked as well, as `make --include-drafts` builds them.
+    fn check_required_encryption(
+        &mut self,
+        registry: &OwnedRegistry<ReadLock>,
+        root_record: &OwnedRecord,
+    ) {
+        let unencrypted_paths = root_record
+            .iter()
+            .filter(|(_, record)| {
+                record.config.parameters.encryption.is_none()
+                    && registry
+                        .require_encryption
+                        .is_required(std::ptr::eq(*record, root_record))
+            })
+            .map(|(record_path, _)| record_path.to_string())
+            .collect::<Vec<_>>();
+
+        if unencrypted_paths.is_empty() {
+            self.push(
+                "required encryption",
+                CheckStatus::Pass,
+                "all records are encrypted as required",
+            );
+        } else {
+            self.push(
+                "required encryption",
+                CheckStatus::Fail,
+                format!(
+                    "records required to be encrypted are not: {}",
+                    unencrypted_paths.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Warns about successive records of the same record whose names only differ in case, as they
+    /// are easily confused, and cannot be stored in separate directories on case-insensitive file
+    /// systems.
+    fn check_record_names(&mut self, root_record: &OwnedRecord) {
+        let mut ambiguous_paths = Vec::new();
+
+        for (record_path, record) in root_record.iter() {
+            let mut names = HashMap::new();
+
+            for successive_record in &record.successive_records {
+                let name = successive_record.config.name.to_ascii_lowercase();
+
+                if let Some(other_name) = names.insert(name, &successive_record.config.name) {
+                    ambiguous_paths.push(format!(
+                        "{record_path}: {:?} and {:?}",
+                        String::from_utf8_lossy(other_name),
+                        String::from_utf8_lossy(&successive_record.config.name),
+                    ));
+                }
+            }
+        }
+
+        if ambiguous_paths.is_empty() {
+            self.push(
+                "record names",
+                CheckStatus::Pass,
+                "no ambiguous record names",
+            );
+        } else {
+            self.push(
+                "record names",
+                CheckStatus::Warn,
+                format!(
+                    "record names only differing in case: {}",
+                    ambiguous_paths.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Warns about files in record directories which are neither configs nor data files, and are
+    /// therefore ignored by builds.
+    async fn check_stray_files(&mut self, root_record: &OwnedRecord) -> Result<()> {
+        let mut stray_file_paths = Vec::new();
+
+        for (_, record) in root_record.iter() {
+            // The directory belongs to the parent record.
+            if record.declared_in_manifest {
+                continue;
+            }
+
+            let config_path =
+                OwnedRecord::get_config_path_from_record_directory_path(&record.directory_path);
+            let manifest_path =
+                OwnedRecord::get_manifest_path_from_record_directory_path(&record.directory_path);
+            let mut read_dir = tokio::fs::read_dir(&record.directory_path).await?;
+
+            while let Some(entry) = read_dir.next_entry().await? {
+                let path = entry.path();
+
+                if entry.file_type().await?.is_file()
+                    && path != config_path
+                    && path != manifest_path
+                    && OwnedRecord::parse_data_file_name(&entry.file_name()).is_none()
+                {
+                    stray_file_paths.push(path);
+                }
+            }
+        }
+
+        if stray_file_paths.is_empty() {
+            self.push("stray files", CheckStatus::Pass, "no stray files");
+        } else {
+            stray_file_paths.sort();
+            self.push(
+                "stray files",
+                CheckStatus::Warn,
+                format!("files ignored by builds: {stray_file_paths:?}"),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether users other than the owner have any permissions on the file.
+#[cfg(unix)]
+fn is_accessible_to_others(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o077 != 0
+}
+
+#[cfg(not(unix))]
+fn is_accessible_to_others(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
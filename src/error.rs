@@ -2,8 +2,96 @@ use std::path::PathBuf;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error("Cannot derive a record name from the directory {directory:?}, as its name is empty or consists solely of whitespace")]
+    BlankRecordName { directory: PathBuf },
+    #[error("Refusing to {operation} without confirmation, pass `--assume-yes` to proceed")]
+    ConfirmationRequired { operation: String },
+    #[error("Declined to {operation}")]
+    ConfirmationDeclined { operation: String },
+    #[error("Refusing to rotate the root predecessor nonce, which invalidates every record of the registry, without `--i-know-this-invalidates-everything`")]
+    RootNonceRotationNotAcknowledged,
+    #[error("{failed} health checks failed")]
+    DoctorChecksFailed { failed: usize },
+    #[error("The build exceeded its time limit and was aborted")]
+    BuildTimedOut,
+    #[error("Record {record_path} is not encrypted, but the registry config requires it to be")]
+    EncryptionRequired { record_path: String },
     #[error("Duplicate successive record {name:?} of parent {parent:?}")]
     DuplicateSuccessiveRecord { parent: PathBuf, name: Vec<u8> },
+    #[error(
+        "The name {name:?} of a successive record of {parent:?} is empty after transforming it"
+    )]
+    EmptyTransformedRecordName { parent: PathBuf, name: Vec<u8> },
     #[error("Registry already exists at path {path:?}")]
     RegistryAlreadyExists { path: PathBuf },
+    #[error("The config {path:?} was replaced since the registry was loaded, load it again to read the new config")]
+    ConfigReplaced { path: PathBuf },
+    #[error("Data of the text record {record_path} is not valid UTF-8 after byte {valid_up_to}")]
+    InvalidUtf8Data {
+        record_path: String,
+        valid_up_to: usize,
+    },
+    #[error("Signing key at path {path:?} is not a valid PKCS#8 PEM-encoded key")]
+    MalformedSigningKey { path: PathBuf },
+    #[error("The root record in {directory:?} has neither data nor successive records, set `allow_empty = true` in the registry config to build it anyway")]
+    EmptyRegistry { directory: PathBuf },
+    #[error("Output directory {output_directory:?} overlaps the root record directory {root_record_directory:?}")]
+    OutputOverlapsSource {
+        output_directory: PathBuf,
+        root_record_directory: PathBuf,
+    },
+    #[error("The extra staging directory {directory:?} overlaps {other:?}, which replacing it would remove")]
+    ExtraStagingOverlaps { directory: PathBuf, other: PathBuf },
+    #[error("No record found at path {path:?}")]
+    RecordNotFound { path: String },
+    #[error("Data reference {data_ref:?} does not point to a file within the registry directory")]
+    InvalidDataRef { data_ref: PathBuf },
+    #[error("Data reference {data_ref:?} points to the registry config, a signing key or an output directory")]
+    ReservedDataRef { data_ref: PathBuf },
+    #[error("The output registry config is incompatible with the source registry, as its `{field}` differs")]
+    OutputRegistryConfigMismatch { field: &'static str },
+    #[error("The record in {directory:?} is declared intermediate, but has data files")]
+    IntermediateRecordHasData { directory: PathBuf },
+    #[error("The record in {directory:?} is declared not intermediate, but has no data files")]
+    RecordDataMissing { directory: PathBuf },
+    #[error("The directory {path:?} would be ignored, as its parent record declares its successive records in a manifest")]
+    SubdirectoryBesideManifest { path: PathBuf },
+    #[error("The config {path:?} contains the unknown keys {keys:?}")]
+    UnknownConfigKeys { path: PathBuf, keys: Vec<String> },
+    #[error(
+        "The indexed data files of the record in {path:?} have different extensions {extensions:?}"
+    )]
+    InconsistentDataExtension {
+        path: PathBuf,
+        extensions: Vec<String>,
+    },
+    #[error("The record in {path:?} does not specify the parameters {fields:?}")]
+    UnresolvedParameters {
+        path: PathBuf,
+        fields: Vec<&'static str>,
+    },
+    #[error("The record in {path:?} ignores the unknown metadata field {field:?}")]
+    UnknownMetadataField { path: PathBuf, field: String },
+    #[error("Invalid splitting strategy in {path:?}: {reason}")]
+    InvalidSplittingStrategy { path: PathBuf, reason: &'static str },
+    #[error("Invalid padding strategy in {path:?}: {reason}")]
+    InvalidPaddingStrategy { path: PathBuf, reason: &'static str },
+    #[error("Split offsets {offsets:?} of the record in {directory:?} must be strictly increasing and lie within its data of length {data_length}")]
+    InvalidSplitOffsets {
+        directory: PathBuf,
+        offsets: Vec<u64>,
+        data_length: u64,
+    },
+    #[error("Symbolic link cycle detected at path {path:?}")]
+    SymlinkCycle { path: PathBuf },
+    #[error(
+        "Failed to decrypt the secrets of the registry config, the passphrase may be incorrect"
+    )]
+    SecretsDecryptionFailed,
+    #[error("Segment of length {segment_length} of record {record_path} exceeds the segment padding of {segment_padding_to_bytes} bytes")]
+    SegmentExceedsPadding {
+        record_path: String,
+        segment_length: usize,
+        segment_padding_to_bytes: u64,
+    },
 }
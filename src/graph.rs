@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+use crate::record::OwnedRecord;
+
+/// The hierarchy of records in a source directory, with an edge from each record to each of its
+/// successive records.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RecordGraph {
+    /// Nodes in the pre-order of the record tree, so that the root record is the first node.
+    pub nodes: Vec<RecordGraphNode>,
+    /// Pairs of indices of a parent record and its successive record in `nodes`.
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecordGraphNode {
+    pub name: String,
+    /// The name of the encryption algorithm of the record, or `"none"` for unencrypted records.
+    pub encryption: String,
+}
+
+impl RecordGraph {
+    pub fn from_root_record(root_record: &OwnedRecord) -> Self {
+        let mut graph = Self::default();
+
+        graph.add_record(root_record, None);
+
+        graph
+    }
+
+    fn add_record(&mut self, record: &OwnedRecord, parent_index: Option<usize>) {
+        let index = self.nodes.len();
+
+        self.nodes.push(RecordGraphNode {
+            name: String::from_utf8_lossy(&record.config.name).into_owned(),
+            encryption: match record.config.parameters.encryption.as_ref() {
+                Some(encryption) => format!("{:?}", encryption.algorithm),
+                None => "none".to_owned(),
+            },
+        });
+
+        if let Some(parent_index) = parent_index {
+            self.edges.push((parent_index, index));
+        }
+
+        for successive_record in &record.successive_records {
+            self.add_record(successive_record, Some(index));
+        }
+    }
+
+    /// Renders the graph as a Graphviz DOT document.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph records {\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = format!("{}\nencryption: {}", node.name, node.encryption);
+            let style = if node.encryption == "none" {
+                "dashed"
+            } else {
+                "solid"
+            };
+
+            writeln!(
+                dot,
+                "    n{index} [label=\"{}\", style={style}];",
+                escape_dot(&label)
+            )
+            .unwrap();
+        }
+
+        for (parent_index, child_index) in &self.edges {
+            writeln!(dot, "    n{parent_index} -> n{child_index};").unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a Mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                mermaid,
+                "    n{index}[\"{}<br/>encryption: {}\"]",
+                escape_mermaid(&node.name),
+                escape_mermaid(&node.encryption)
+            )
+            .unwrap();
+        }
+
+        for (parent_index, child_index) in &self.edges {
+            writeln!(mermaid, "    n{parent_index} --> n{child_index}").unwrap();
+        }
+
+        mermaid
+    }
+}
+
+fn escape_dot(string: &str) -> String {
+    string
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_mermaid(string: &str) -> String {
+    string
+        .replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
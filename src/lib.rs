@@ -1,46 +1,129 @@
 #![feature(array_windows)]
 
-use chrono::DateTime;
-use color_eyre::eyre::OptionExt;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::WrapErr;
 use futures::{future::BoxFuture, FutureExt};
 use record::OwnedRecord;
 use registry::OwnedRegistry;
 use rrr::{
     record::{
-        segment::{RecordVersion, SegmentEncryption},
-        HashedRecordKey, Record, RecordKey, RecordMetadata, RecordName, RecordPath,
-        SuccessionNonce,
-    },
-    registry::Registry,
-    utils::{
-        fd_lock::{FileLock, WriteLock},
-        serde::BytesOrAscii,
+        segment::RecordVersion, HashedRecordKey, Record, RecordKey, RecordMetadata, RecordName,
+        RecordPath, SuccessionNonce,
     },
+    utils::{fd_lock::FileLock, serde::BytesOrAscii},
+};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Instant, SystemTime},
 };
 use tokio::io::AsyncReadExt;
 
 pub mod assets;
+pub mod bench;
+pub mod config_diff;
+pub mod doctor;
 pub mod error;
+pub mod graph;
+pub mod name_transform;
 pub mod owned;
+pub mod stats;
+pub mod user_config;
 pub mod util;
+pub mod writer;
 
 #[cfg(feature = "cmd")]
 pub mod cmd;
 
+use error::Error;
+use name_transform::RecordNameTransform;
 pub use owned::*;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+use writer::RegistryWriter;
 
 #[derive(Default)]
 pub struct MakeRecursiveStatistics {
     pub records_created: usize,
     pub records_updated: usize,
     pub records_unchanged: usize,
+    /// Records which were intentionally not built, along with their successive records.
+    pub records_skipped: usize,
+    /// Every processed record, in the order in which it was processed.
+    pub processed_records: Vec<ProcessedRecord>,
+}
+
+/// The effect of building a record on the output registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordChange {
+    Created,
+    Updated,
+    Unchanged,
+    /// The record was not built, because it is a draft.
+    Skipped,
+}
+
+/// A record processed by [`make_recursive`].
+#[derive(Clone, Debug)]
+pub struct ProcessedRecord {
+    pub record_path: String,
+    /// The succession nonce of the parent record, or the root predecessor nonce for the root record.
+    pub predecessor_nonce: SuccessionNonce,
+    pub change: RecordChange,
+}
+
+/// Options affecting how records are built by [`make_recursive`].
+#[derive(Clone, Debug, Default)]
+pub struct MakeRecursiveOptions {
+    /// Whether suspicious record configurations should be treated as errors rather than warnings.
+    pub strict: bool,
+    /// Whether per-record changes should only be logged at the debug level, leaving just the
+    /// summary at the info level.
+    pub summary_only: bool,
+    /// The instant after which no further records are built, aborting the build instead.
+    /// It is checked before each record is built, so that no record is left partially written.
+    pub deadline: Option<Instant>,
+    /// Whether records marked as drafts should be built, rather than skipped.
+    pub include_drafts: bool,
+    /// Applied to the name of each record except for the root record, before the record is built.
+    pub name_transform: Option<Arc<dyn RecordNameTransform>>,
+    /// Whether the data of records marked as text should be validated to be UTF-8.
+    pub validate_utf8_data: bool,
+    /// If set, records whose config and data files were not modified after this time are neither
+    /// read nor compared to the output registry, but assumed to be unchanged, as long as the output
+    /// registry has a version of them.
+    pub since_mtime: Option<SystemTime>,
+}
+
+/// Returns the lengths of the segments that data of length `data_len` is split into according to
+/// `split_at`, where each element of `split_at` is the length of one of the leading segments.
+pub(crate) fn segment_lengths(
+    data_len: usize,
+    split_at: &[usize],
+) -> impl Iterator<Item = usize> + '_ {
+    let split_len = split_at.iter().sum::<usize>();
+
+    split_at
+        .iter()
+        .copied()
+        .chain(std::iter::once(data_len.saturating_sub(split_len)))
+}
+
+/// Sets the metadata `field` to the same placeholder in every record, so that records which only
+/// differ in ignored fields, including fields present in only one of them, compare equal.
+fn mask_metadata_field(metadata: &mut RecordMetadata, field: &str) {
+    match field {
+        "created_at" => {
+            metadata.insert_created_at(DateTime::<Utc>::UNIX_EPOCH.fixed_offset());
+        }
+        _ => unreachable!("unknown metadata fields are rejected when loading the record config"),
+    }
 }
 
 /// If `output_record` differs from the latest version of the record in the `output_registry`, saves
-/// the `output_record` as a new version.
+/// the `output_record` as a new version. Metadata fields listed in `version_ignore_metadata` of
+/// the record config are left out of the comparison.
 pub async fn save_record_versioned<L: FileLock>(
-    output_registry: &mut Registry<WriteLock>,
+    output_registry: &mut impl RegistryWriter,
     input_registry: &OwnedRegistry<L>,
     input_record: &OwnedRecord,
     max_version_lookahead: u64,
@@ -49,40 +132,46 @@ pub async fn save_record_versioned<L: FileLock>(
     output_record: &Record,
     hashed_key: &HashedRecordKey,
     split_at: &[usize],
+    options: &MakeRecursiveOptions,
     stats: &mut MakeRecursiveStatistics,
-) -> color_eyre::Result<()> {
-    let existing_versions = output_registry
-        .list_record_versions(
+) -> color_eyre::Result<RecordChange> {
+    let latest_existing_version = output_registry
+        .read_latest_record_version(
             hashed_key,
             max_version_lookahead,
             max_collision_resolution_attempts,
         )
         .await?;
+    let max_segment_length = segment_lengths(output_record.data.0.len(), split_at)
+        .max()
+        .unwrap_or_default();
     let encryption = input_record
         .config
         .parameters
         .encryption
         .as_ref()
-        .map(SegmentEncryption::from);
+        .map(|encryption| encryption.to_segment_encryption(max_segment_length));
 
-    if let Some(latest_existing_version) = existing_versions.last() {
-        let latest_existing_version_record = Record::read_version_with_nonce(
-            output_registry,
-            hashed_key,
-            latest_existing_version.record_version,
-            latest_existing_version.record_nonce,
-        )
-        .await?
-        .ok_or_eyre("Failed to load the latest version of a record.")?;
+    if let Some((latest_existing_version, mut latest_existing_version_record)) =
+        latest_existing_version
+    {
+        let mut compared_output_record = output_record.clone();
+
+        for field in &input_record.config.version_ignore_metadata {
+            mask_metadata_field(&mut latest_existing_version_record.metadata, field);
+            mask_metadata_field(&mut compared_output_record.metadata, field);
+        }
 
-        if &latest_existing_version_record.record == output_record {
-            debug!(version = %latest_existing_version.record_version.0, %record_path, "Record unchanged, skipping.");
+        if latest_existing_version_record == compared_output_record {
+            debug!(version = %latest_existing_version.0, %record_path, "Record unchanged, skipping.");
             stats.records_unchanged += 1;
+
+            Ok(RecordChange::Unchanged)
         } else {
-            let new_version = RecordVersion(latest_existing_version.record_version.0 + 1);
+            let new_version = RecordVersion(latest_existing_version.0 + 1);
 
             debug!(
-                version_previous = latest_existing_version.record_version.0,
+                version_previous = latest_existing_version.0,
                 version_current = new_version.0,
                 %record_path,
                 "Record changed, writing new version."
@@ -96,17 +185,27 @@ pub async fn save_record_versioned<L: FileLock>(
                     max_collision_resolution_attempts,
                     split_at,
                     encryption.as_ref(),
-                    false,
                 )
                 .await?;
             stats.records_updated += 1;
 
-            info!(
-                version_previous = latest_existing_version.record_version.0,
-                version_current = new_version.0,
-                %record_path,
-                "New version of record created."
-            );
+            if options.summary_only {
+                debug!(
+                    version_previous = latest_existing_version.0,
+                    version_current = new_version.0,
+                    %record_path,
+                    "New version of record created."
+                );
+            } else {
+                info!(
+                    version_previous = latest_existing_version.0,
+                    version_current = new_version.0,
+                    %record_path,
+                    "New version of record created."
+                );
+            }
+
+            Ok(RecordChange::Updated)
         }
     } else {
         output_registry
@@ -118,79 +217,267 @@ pub async fn save_record_versioned<L: FileLock>(
                 max_collision_resolution_attempts,
                 split_at,
                 encryption.as_ref(),
-                false,
             )
             .await?;
         stats.records_created += 1;
 
-        info!(%record_path, "New record created.");
+        if options.summary_only {
+            debug!(%record_path, "New record created.");
+        } else {
+            info!(%record_path, "New record created.");
+        }
+
+        Ok(RecordChange::Created)
     }
+}
 
-    Ok(())
+/// Reads the data of `input_record`, and builds the record to be saved in the output registry.
+/// Returns the record along with the lengths of its leading segments.
+async fn build_output_record(
+    input_record: &OwnedRecord,
+    record_path: &RecordPath,
+    options: &MakeRecursiveOptions,
+) -> color_eyre::Result<(Record, Vec<usize>)> {
+    // Intermediate records carry no data, which is validated when loading the record.
+    let (mut data, mut split_at) = match input_record.read().await? {
+        Some(mut read_result) => {
+            let mut data = Vec::new();
+            read_result.read.read_to_end(&mut data).await?;
+            (data, read_result.split_at.unwrap_or_default())
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+
+    if options.validate_utf8_data && input_record.config.text {
+        if let Err(error) = std::str::from_utf8(&data) {
+            return Err(Error::InvalidUtf8Data {
+                record_path: record_path.to_string(),
+                valid_up_to: error.valid_up_to(),
+            }
+            .into());
+        }
+    }
+
+    if let Some(line_ending) = input_record
+        .config
+        .parameters
+        .normalize_line_endings
+        .filter(|_| input_record.config.text)
+    {
+        // Normalize each segment separately, so that the segments keep their boundaries.
+        let segments = segment_lengths(data.len(), &split_at)
+            .scan(0, |offset, segment_length| {
+                let segment_end = (*offset + segment_length).min(data.len());
+                let segment = &data[*offset..segment_end];
+                *offset = segment_end;
+                Some(line_ending.normalize(segment))
+            })
+            .collect::<Vec<_>>();
+
+        split_at = segments[..segments.len() - 1]
+            .iter()
+            .map(Vec::len)
+            .collect();
+        data = segments.concat();
+    }
+
+    let output_record = Record {
+        metadata: {
+            let mut metadata = RecordMetadata::default();
+
+            if let Some(created_at) = input_record.config.metadata.get_created_at()? {
+                metadata.insert_created_at(created_at);
+            }
+
+            metadata
+        },
+        data: BytesOrAscii(data),
+    };
+
+    if let Some(encryption) = input_record.config.parameters.encryption.as_ref() {
+        let max_segment_length = segment_lengths(output_record.data.0.len(), &split_at)
+            .max()
+            .unwrap_or_default();
+        let segment_padding_to_bytes = encryption.segment_padding_to_bytes_for(max_segment_length);
+
+        for segment_length in segment_lengths(output_record.data.0.len(), &split_at) {
+            if segment_length as u64 > segment_padding_to_bytes {
+                if options.strict {
+                    return Err(Error::SegmentExceedsPadding {
+                        record_path: record_path.to_string(),
+                        segment_length,
+                        segment_padding_to_bytes,
+                    }
+                    .into());
+                }
+
+                warn!(
+                    %record_path,
+                    segment_length,
+                    segment_padding_to_bytes,
+                    "Record segment is larger than the segment padding, the padding has no effect."
+                );
+                break;
+            }
+        }
+    }
+
+    Ok((output_record, split_at))
 }
 
-pub fn make_recursive<'a, L: FileLock>(
-    output_registry: &'a mut Registry<WriteLock>,
+pub fn make_recursive<'a, L: FileLock, W: RegistryWriter>(
+    output_registry: &'a mut W,
     input_registry: &'a OwnedRegistry<L>,
     input_record: &'a OwnedRecord,
     predecessor_nonce: &'a SuccessionNonce,
     max_version_lookahead: u64,
     max_collision_resolution_attempts: u64,
+    options: &'a MakeRecursiveOptions,
     // Record path excluding the `input_record`.
     path_to_parent_record: &'a mut Vec<RecordName>,
     stats: &'a mut MakeRecursiveStatistics,
 ) -> BoxFuture<'a, color_eyre::Result<()>> {
     async move {
-        let mut read_result = input_record.read().await?.expect("Data not found.");
-        let data = {
-            let mut data = Vec::new();
-            read_result.read.read_to_end(&mut data).await?;
-            data
-        };
+        if options
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Error::BuildTimedOut.into());
+        }
 
-        let output_record = Record {
-            metadata: {
-                let mut metadata = RecordMetadata::default();
+        // The root record is exempt, as readers derive its key from the untransformed
+        // `root_record_name`, see `OwnedRegistry::get_root_record_key`.
+        let record_name = match options.name_transform.as_ref() {
+            Some(name_transform) if !path_to_parent_record.is_empty() => {
+                name_transform.transform(&input_record.config.name)
+            }
+            _ => input_record.config.name.to_vec(),
+        };
+        let record_path = {
+            let mut record_path = path_to_parent_record.clone();
+            record_path.push(RecordName::from(record_name.clone()));
+            RecordPath::try_from(record_path).unwrap()
+        };
 
-                if let Some(created_at) = input_record.config.metadata.created_at.as_ref() {
-                    let created_at_chrono = DateTime::parse_from_rfc3339(&created_at.to_string())?;
+        if input_record.config.draft && !options.include_drafts {
+            stats.records_skipped += input_record.count_records();
+            stats.processed_records.push(ProcessedRecord {
+                record_path: record_path.to_string(),
+                predecessor_nonce: predecessor_nonce.clone(),
+                change: RecordChange::Skipped,
+            });
+            debug!(
+                directory = ?input_record.directory_path,
+                "Skipping draft record along with its successive records."
+            );
+            return Ok(());
+        }
 
-                    metadata.insert_created_at(created_at_chrono);
-                }
+        // Checked before hashing the key, which is slow by design.
+        if input_record.config.parameters.encryption.is_none()
+            && input_registry
+                .config
+                .require_encryption
+                .is_required(path_to_parent_record.is_empty())
+        {
+            return Err(Error::EncryptionRequired {
+                record_path: record_path.to_string(),
+            }
+            .into());
+        }
 
-                metadata
-            },
-            data: BytesOrAscii(data),
-        };
+        let error_context = || format!("Failed to process record {record_path}");
         let key = RecordKey {
-            record_name: RecordName::from(input_record.config.name.to_vec()),
+            record_name: RecordName::from(record_name),
             predecessor_nonce: predecessor_nonce.clone(),
         };
-        let hashed_key = key.hash(&input_registry.hash).await?;
-        let record_path = {
-            let mut record_path = path_to_parent_record.clone();
-            record_path.push(key.record_name.clone());
-            RecordPath::try_from(record_path).unwrap()
+        let hashed_key = key
+            .hash(&input_registry.hash)
+            .await
+            .wrap_err_with(error_context)?;
+
+        let unmodified = match options.since_mtime {
+            Some(since_mtime) => {
+                !input_record
+                    .modified_since(since_mtime)
+                    .await
+                    .wrap_err_with(error_context)?
+                    // Records missing from the output registry, e.g. after a failed build, are
+                    // built regardless of their modification time.
+                    && output_registry
+                        .read_latest_record_version(
+                            &hashed_key,
+                            max_version_lookahead,
+                            max_collision_resolution_attempts,
+                        )
+                        .await
+                        .wrap_err_with(error_context)?
+                        .is_some()
+            }
+            None => false,
         };
+        let change = if unmodified {
+            stats.records_unchanged += 1;
+            debug!(%record_path, "Record not modified since the given time, skipping.");
+            RecordChange::Unchanged
+        } else {
+            let (output_record, split_at) =
+                build_output_record(input_record, &record_path, options)
+                    .await
+                    .wrap_err_with(error_context)?;
 
-        save_record_versioned(
-            output_registry,
-            input_registry,
-            input_record,
-            max_version_lookahead,
-            max_collision_resolution_attempts,
-            &record_path,
-            &output_record,
-            &hashed_key,
-            // TODO: Handle `SplittingStrategy::Fill`
-            read_result.split_at.as_deref().unwrap_or_default(),
-            stats,
-        )
-        .await?;
+            save_record_versioned(
+                output_registry,
+                input_registry,
+                input_record,
+                max_version_lookahead,
+                max_collision_resolution_attempts,
+                &record_path,
+                &output_record,
+                &hashed_key,
+                // TODO: Handle `SplittingStrategy::Fill`
+                &split_at,
+                options,
+                stats,
+            )
+            .await
+            .wrap_err_with(error_context)?
+        };
+
+        stats.processed_records.push(ProcessedRecord {
+            record_path: record_path.to_string(),
+            predecessor_nonce: predecessor_nonce.clone(),
+            change,
+        });
 
         let succession_nonce = hashed_key
             .derive_succession_nonce(&input_registry.config.kdf)
-            .await?;
+            .await
+            .wrap_err_with(error_context)?;
+
+        if let Some(name_transform) = options.name_transform.as_ref() {
+            let mut successive_record_names = HashSet::new();
+
+            for successive_record in &input_record.successive_records {
+                let name = name_transform.transform(&successive_record.config.name);
+
+                if name.is_empty() {
+                    return Err(Error::EmptyTransformedRecordName {
+                        parent: input_record.directory_path.clone(),
+                        name: successive_record.config.name.to_vec(),
+                    }
+                    .into());
+                }
+
+                if !successive_record_names.insert(name.clone()) {
+                    return Err(Error::DuplicateSuccessiveRecord {
+                        parent: input_record.directory_path.clone(),
+                        name,
+                    }
+                    .into());
+                }
+            }
+        }
 
         {
             path_to_parent_record.push(key.record_name.clone());
@@ -203,6 +490,7 @@ pub fn make_recursive<'a, L: FileLock>(
                     &succession_nonce,
                     max_version_lookahead,
                     max_collision_resolution_attempts,
+                    options,
                     path_to_parent_record,
                     stats,
                 )
@@ -0,0 +1,70 @@
+use std::fmt::Debug;
+
+/// Transforms the names of records before they are used to build the output registry.
+/// The transformation must be deterministic, so that records are found under the same name in
+/// subsequent builds.
+pub trait RecordNameTransform: Debug + Send + Sync {
+    fn transform(&self, name: &[u8]) -> Vec<u8>;
+}
+
+/// Converts names to lowercase. Bytes which are not part of valid UTF-8 are left unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LowercaseNameTransform;
+
+impl RecordNameTransform for LowercaseNameTransform {
+    fn transform(&self, name: &[u8]) -> Vec<u8> {
+        match std::str::from_utf8(name) {
+            Ok(name) => name.to_lowercase().into_bytes(),
+            Err(_) => name.to_ascii_lowercase(),
+        }
+    }
+}
+
+/// Converts names to lowercase, and replaces each run of characters other than letters and digits
+/// with a single `-`, trimming it from both ends, e.g. `Hello World` becomes `hello-world`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SlugifyNameTransform;
+
+impl RecordNameTransform for SlugifyNameTransform {
+    fn transform(&self, name: &[u8]) -> Vec<u8> {
+        let name = String::from_utf8_lossy(name).to_lowercase();
+        let mut slug = String::with_capacity(name.len());
+
+        for word in name.split(|c: char| !c.is_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+
+            if !slug.is_empty() {
+                slug.push('-');
+            }
+
+            slug.push_str(word);
+        }
+
+        slug.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify() {
+        let cases: &[(&str, &str)] = &[
+            ("Hello World", "hello-world"),
+            ("  Leading and trailing  ", "leading-and-trailing"),
+            ("multiple---separators__here", "multiple-separators-here"),
+            ("Ünïcode Lëtters", "ünïcode-lëtters"),
+            ("", ""),
+        ];
+
+        for (name, expected_slug) in cases {
+            assert_eq!(
+                SlugifyNameTransform.transform(name.as_bytes()),
+                expected_slug.as_bytes()
+            );
+        }
+    }
+}
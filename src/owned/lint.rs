@@ -0,0 +1,128 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use color_eyre::Result;
+
+use crate::{
+    record::{OwnedRecord, OwnedRecordConfig, SplittingStrategy},
+    segment_lengths,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    /// The configuration is unusual, but likely intentional.
+    Info,
+    /// The configuration is likely a mistake.
+    Warning,
+}
+
+impl Display for LintSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A suspicious, but valid, record configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+impl Lint {
+    fn new(severity: LintSeverity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl OwnedRecordConfig {
+    /// Checks the resolved config for suspicious settings which do not depend on the record's data.
+    /// `is_root` tells whether the config is that of the root record.
+    pub fn lint(&self, is_root: bool) -> Vec<Lint> {
+        let mut lints = Vec::new();
+
+        if self.parameters.encryption.is_none() && !is_root {
+            lints.push(Lint::new(
+                LintSeverity::Warning,
+                "the record is not encrypted, even though it is not the root record",
+            ));
+        }
+
+        if let Some(encryption) = self.parameters.encryption.as_ref() {
+            if encryption.padding_strategy.is_fixed() && encryption.segment_padding_to_bytes == 0 {
+                lints.push(Lint::new(
+                    LintSeverity::Info,
+                    "the segment padding is 0 bytes, so segment lengths are not concealed",
+                ));
+            }
+        }
+
+        if let Some(created_at) = self.metadata.created_at.as_ref() {
+            if let Ok(created_at) = DateTime::parse_from_rfc3339(&created_at.to_string()) {
+                if created_at > Utc::now() {
+                    lints.push(Lint::new(
+                        LintSeverity::Warning,
+                        format!("the creation time {created_at} lies in the future"),
+                    ));
+                }
+            }
+        }
+
+        lints
+    }
+}
+
+impl OwnedRecord {
+    /// Checks the record for suspicious settings, including those of its config and those which
+    /// depend on its data files. `is_root` tells whether the record is the root record.
+    pub async fn lint(&self, is_root: bool) -> Result<Vec<Lint>> {
+        let mut lints = self.config.lint(is_root);
+        let data_paths = self.get_data_paths().await?;
+
+        if let (SplittingStrategy::Manual {}, [_]) =
+            (&self.config.parameters.splitting_strategy, &data_paths[..])
+        {
+            lints.push(Lint::new(
+                LintSeverity::Info,
+                "the manual splitting strategy is used with a single data file, resulting in a single segment",
+            ));
+        }
+
+        if let Some(encryption) = self.config.parameters.encryption.as_ref() {
+            let mut data_length = 0;
+
+            for data_path in &data_paths {
+                data_length += tokio::fs::metadata(data_path).await?.len() as usize;
+            }
+
+            let split_at = self
+                .read()
+                .await?
+                .and_then(|read_success| read_success.split_at)
+                .unwrap_or_default();
+            let max_segment_length = segment_lengths(data_length, &split_at).max();
+
+            if let Some(max_segment_length) = max_segment_length {
+                let segment_padding_to_bytes =
+                    encryption.segment_padding_to_bytes_for(max_segment_length);
+
+                if max_segment_length as u64 > segment_padding_to_bytes {
+                    lints.push(Lint::new(
+                        LintSeverity::Warning,
+                        format!(
+                            "a segment of {max_segment_length} bytes is larger than the segment padding of {segment_padding_to_bytes} bytes"
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(lints)
+    }
+}
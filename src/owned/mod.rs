@@ -1,2 +1,5 @@
+pub mod lint;
 pub mod record;
 pub mod registry;
+pub mod secrets;
+pub mod spec;
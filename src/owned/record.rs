@@ -1,22 +1,34 @@
-use chrono::{DateTime, Utc};
-use color_eyre::{
-    eyre::{bail, eyre},
-    Result,
-};
+use chrono::{DateTime, FixedOffset, Utc};
+use color_eyre::{eyre::bail, Result};
 use core::str;
 use futures::future::{BoxFuture, FutureExt};
-use rrr::{crypto::encryption::EncryptionAlgorithm, record::segment::SegmentEncryption};
+use rrr::{
+    crypto::encryption::EncryptionAlgorithm,
+    record::{segment::SegmentEncryption, RecordName, RecordPath},
+};
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::{
+    borrow::Cow,
     collections::HashSet,
+    ffi::OsStr,
     fmt::Debug,
     path::{Path, PathBuf},
     str::FromStr,
+    time::SystemTime,
 };
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tracing::warn;
+use unicode_normalization::UnicodeNormalization;
 
-use crate::{error::Error, registry::OwnedRegistryConfig, util::serde::DoubleOption};
+use crate::{
+    error::Error,
+    registry::OwnedRegistryConfig,
+    util::serde::{
+        deserialize_double_option, ensure_no_unknown_keys, find_ignored_keys, DoubleOption,
+        ExplicitOption,
+    },
+};
 
 pub trait Unresolved: Sized + Default + From<Self::Resolved> {
     type Resolved: Sized;
@@ -29,6 +41,8 @@ pub trait Unresolved: Sized + Default + From<Self::Resolved> {
 pub struct OwnedRecordConfigEncryptionUnresolved {
     pub algorithm: Option<EncryptionAlgorithm>,
     pub segment_padding_to_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding_strategy: Option<PaddingStrategy>,
 }
 
 impl Unresolved for OwnedRecordConfigEncryptionUnresolved {
@@ -40,18 +54,23 @@ impl Unresolved for OwnedRecordConfigEncryptionUnresolved {
             segment_padding_to_bytes: self
                 .segment_padding_to_bytes
                 .or(fallback.segment_padding_to_bytes),
+            padding_strategy: self.padding_strategy.or(fallback.padding_strategy),
         }
     }
 
     fn resolve(self) -> Result<Self::Resolved, Self> {
-        if let Self {
-            algorithm: Some(algorithm),
-            segment_padding_to_bytes: Some(segment_padding_to_bytes),
-        } = self
+        let padding_strategy = self.padding_strategy.clone().unwrap_or_default();
+        let segment_padding_to_bytes = self
+            .segment_padding_to_bytes
+            .or_else(|| padding_strategy.min_padding_to_bytes());
+
+        if let (Some(algorithm), Some(segment_padding_to_bytes)) =
+            (self.algorithm, segment_padding_to_bytes)
         {
             Ok(Self::Resolved {
                 algorithm,
                 segment_padding_to_bytes,
+                padding_strategy,
             })
         } else {
             Err(self)
@@ -64,6 +83,8 @@ impl From<OwnedRecordConfigEncryption> for OwnedRecordConfigEncryptionUnresolved
         Self {
             algorithm: Some(value.algorithm),
             segment_padding_to_bytes: Some(value.segment_padding_to_bytes),
+            padding_strategy: Some(value.padding_strategy)
+                .filter(|padding_strategy| !padding_strategy.is_fixed()),
         }
     }
 }
@@ -71,7 +92,14 @@ impl From<OwnedRecordConfigEncryption> for OwnedRecordConfigEncryptionUnresolved
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct OwnedRecordConfigParametersUnresolved {
     pub splitting_strategy: Option<SplittingStrategy>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub encryption: DoubleOption<OwnedRecordConfigEncryptionUnresolved>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalize_line_endings: Option<LineEnding>,
 }
 
 impl Unresolved for OwnedRecordConfigParametersUnresolved {
@@ -81,6 +109,9 @@ impl Unresolved for OwnedRecordConfigParametersUnresolved {
         Self {
             splitting_strategy: self.splitting_strategy.or(fallback.splitting_strategy),
             encryption: self.encryption.or(fallback.encryption),
+            normalize_line_endings: self
+                .normalize_line_endings
+                .or(fallback.normalize_line_endings),
         }
     }
 
@@ -88,6 +119,7 @@ impl Unresolved for OwnedRecordConfigParametersUnresolved {
         if let Self {
             splitting_strategy: Some(splitting_strategy),
             encryption: Some(encryption),
+            normalize_line_endings,
         } = self
         {
             match Option::from(encryption)
@@ -97,10 +129,12 @@ impl Unresolved for OwnedRecordConfigParametersUnresolved {
                 Ok(resolved) => Ok(Self::Resolved {
                     splitting_strategy,
                     encryption: resolved,
+                    normalize_line_endings,
                 }),
                 Err(unresolved) => Err(Self {
                     splitting_strategy: Some(splitting_strategy),
                     encryption: Some(Some(unresolved).into()),
+                    normalize_line_endings,
                 }),
             }
         } else {
@@ -109,6 +143,57 @@ impl Unresolved for OwnedRecordConfigParametersUnresolved {
     }
 }
 
+impl OwnedRecordConfigParametersUnresolved {
+    /// Returns the keys of the `encryption` table of the parameters `value` which are not
+    /// recognized, in the form `encryption.key`. The table is deserialized through a buffer by
+    /// [`deserialize_double_option`], so its unknown keys are not reported by [`find_ignored_keys`]
+    /// when deserializing the parameters.
+    pub fn find_unknown_encryption_keys(value: &toml::Value) -> Result<Vec<String>> {
+        let Some(encryption_value) = value.get("encryption").filter(|value| value.is_table())
+        else {
+            return Ok(Vec::new());
+        };
+
+        Ok(
+            find_ignored_keys::<OwnedRecordConfigEncryptionUnresolved>(encryption_value.clone())?
+                .into_iter()
+                .map(|key| format!("encryption.{key}"))
+                .collect(),
+        )
+    }
+
+    /// Lists the parameters which are not specified.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let mut missing_fields = Vec::new();
+
+        if self.splitting_strategy.is_none() {
+            missing_fields.push("splitting_strategy");
+        }
+
+        match self.encryption.as_ref() {
+            None => missing_fields.push("encryption"),
+            Some(ExplicitOption::None(_)) => (),
+            Some(ExplicitOption::Some(encryption)) => {
+                if encryption.algorithm.is_none() {
+                    missing_fields.push("encryption.algorithm");
+                }
+
+                if encryption.segment_padding_to_bytes.is_none()
+                    && encryption
+                        .padding_strategy
+                        .as_ref()
+                        .and_then(PaddingStrategy::min_padding_to_bytes)
+                        .is_none()
+                {
+                    missing_fields.push("encryption.segment_padding_to_bytes");
+                }
+            }
+        }
+
+        missing_fields
+    }
+}
+
 impl From<OwnedRecordConfigParameters> for OwnedRecordConfigParametersUnresolved {
     fn from(value: OwnedRecordConfigParameters) -> Self {
         Self {
@@ -119,6 +204,7 @@ impl From<OwnedRecordConfigParameters> for OwnedRecordConfigParametersUnresolved
                     .map(OwnedRecordConfigEncryptionUnresolved::from)
                     .into(),
             ),
+            normalize_line_endings: value.normalize_line_endings,
         }
     }
 }
@@ -126,28 +212,107 @@ impl From<OwnedRecordConfigParameters> for OwnedRecordConfigParametersUnresolved
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct OwnedRecordConfigUnresolved {
     pub name: ByteBuf,
+    /// Path to a file to use as the record's data, instead of the data files in the record's
+    /// directory. The path is relative to the registry directory, and must not lead outside of it.
+    pub data_ref: Option<PathBuf>,
+    /// Whether the record is declared to carry no data, and only serves to structure its successive
+    /// records. When omitted, records without data files are considered intermediate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intermediate: Option<bool>,
+    /// Whether the record is a draft, which is excluded from builds along with its successive
+    /// records, unless drafts are explicitly included.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    /// Whether the record's data is expected to be UTF-8 text, rather than binary data.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub text: bool,
+    /// Names of metadata fields which are ignored when deciding whether the record has changed
+    /// since its latest version in the output registry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub version_ignore_metadata: Vec<String>,
     pub metadata: OwnedRecordMetadata,
     #[serde(flatten)]
     pub parameters: OwnedRecordConfigParametersUnresolved,
 }
 
 impl OwnedRecordConfigUnresolved {
+    /// Names of the fields, as used in the record config, excluding the flattened parameters.
+    pub const FIELD_NAMES: &'static [&'static str] = &[
+        "name",
+        "data_ref",
+        "intermediate",
+        "draft",
+        "text",
+        "version_ignore_metadata",
+        "metadata",
+    ];
+
+    /// Returns the keys of the record config `value` which are recognized neither as a field nor as
+    /// a parameter.
+    pub fn find_unknown_keys(value: &toml::Value) -> Result<Vec<String>> {
+        let mut unknown_keys = find_ignored_keys::<Self>(value.clone())?;
+
+        // Keys unknown to the flattened parameters are silently dropped when deserializing the
+        // whole config, so the parameters are deserialized on their own as well.
+        unknown_keys.extend(
+            find_ignored_keys::<OwnedRecordConfigParametersUnresolved>(value.clone())?
+                .into_iter()
+                .filter(|key| {
+                    let field_name = key.split('.').next().unwrap_or_default();
+                    !Self::FIELD_NAMES.contains(&field_name)
+                }),
+        );
+        unknown_keys
+            .extend(OwnedRecordConfigParametersUnresolved::find_unknown_encryption_keys(value)?);
+
+        Ok(unknown_keys)
+    }
+
+    /// Resolves the config of the record in `directory_path`, using `parameters` for any parameters
+    /// the config does not specify.
+    /// Fails if the parameters remain incomplete, or if the resolved parameters are invalid.
     pub fn try_resolve_with(
         self,
         parameters: OwnedRecordConfigParametersUnresolved,
-    ) -> Result<OwnedRecordConfig, OwnedRecordConfigUnresolved> {
-        match self.parameters.or(parameters).resolve() {
-            Ok(resolved) => Ok(OwnedRecordConfig {
-                name: self.name,
-                metadata: self.metadata,
-                parameters: resolved,
-            }),
-            Err(unresolved) => Err(Self {
-                name: self.name,
-                metadata: self.metadata,
-                parameters: unresolved,
-            }),
+        directory_path: &Path,
+    ) -> Result<OwnedRecordConfig> {
+        let parameters = self.parameters.or(parameters);
+        let missing_fields = parameters.missing_fields();
+        let resolved = parameters
+            .resolve()
+            .map_err(|_| Error::UnresolvedParameters {
+                path: directory_path.to_owned(),
+                fields: missing_fields,
+            })?;
+
+        resolved.splitting_strategy.validate(directory_path)?;
+
+        if let Some(encryption) = resolved.encryption.as_ref() {
+            encryption.padding_strategy.validate(directory_path)?;
         }
+
+        if let Some(field) = self
+            .version_ignore_metadata
+            .iter()
+            .find(|field| !OwnedRecordMetadata::FIELD_NAMES.contains(&field.as_str()))
+        {
+            return Err(Error::UnknownMetadataField {
+                path: directory_path.to_owned(),
+                field: field.clone(),
+            }
+            .into());
+        }
+
+        Ok(OwnedRecordConfig {
+            name: self.name,
+            data_ref: self.data_ref,
+            intermediate: self.intermediate,
+            draft: self.draft,
+            text: self.text,
+            version_ignore_metadata: self.version_ignore_metadata,
+            metadata: self.metadata,
+            parameters: resolved,
+        })
     }
 }
 
@@ -155,17 +320,36 @@ impl From<OwnedRecordConfig> for OwnedRecordConfigUnresolved {
     fn from(value: OwnedRecordConfig) -> Self {
         Self {
             name: value.name,
+            data_ref: value.data_ref,
+            intermediate: value.intermediate,
+            draft: value.draft,
+            text: value.text,
+            version_ignore_metadata: value.version_ignore_metadata,
             metadata: value.metadata,
             parameters: value.parameters.into(),
         }
     }
 }
 
-impl From<&OwnedRecordConfigEncryption> for SegmentEncryption {
-    fn from(value: &OwnedRecordConfigEncryption) -> Self {
-        Self {
-            algorithm: value.algorithm,
-            padding_to_bytes: value.segment_padding_to_bytes,
+impl OwnedRecordConfigEncryption {
+    /// The length that the segments of a record are padded to, given the length of its largest
+    /// segment.
+    pub fn segment_padding_to_bytes_for(&self, max_segment_length: usize) -> u64 {
+        match self.padding_strategy {
+            PaddingStrategy::Fixed {} => self.segment_padding_to_bytes,
+            PaddingStrategy::Percentage { percent, min } => {
+                let padded_length =
+                    (max_segment_length as u64 * (100 + percent as u64)).div_ceil(100);
+
+                padded_length.max(min)
+            }
+        }
+    }
+
+    pub fn to_segment_encryption(&self, max_segment_length: usize) -> SegmentEncryption {
+        SegmentEncryption {
+            algorithm: self.algorithm,
+            padding_to_bytes: self.segment_padding_to_bytes_for(max_segment_length),
         }
     }
 }
@@ -175,10 +359,65 @@ pub struct OwnedRecordMetadata {
     pub created_at: Option<toml::value::Datetime>,
 }
 
+impl OwnedRecordMetadata {
+    /// Names of the metadata fields, as used in the record config.
+    pub const FIELD_NAMES: &'static [&'static str] = &["created_at"];
+
+    pub fn get_created_at(&self) -> Result<Option<DateTime<FixedOffset>>> {
+        self.created_at
+            .as_ref()
+            .map(|created_at| DateTime::parse_from_rfc3339(&created_at.to_string()))
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct OwnedRecordConfigEncryption {
     pub algorithm: EncryptionAlgorithm,
+    /// The length that segments are padded to with the fixed padding strategy.
+    /// If omitted with the percentage padding strategy, the minimum padding is used.
     pub segment_padding_to_bytes: u64,
+    #[serde(default, skip_serializing_if = "PaddingStrategy::is_fixed")]
+    pub padding_strategy: PaddingStrategy,
+}
+
+/// How the length that the segments of a record are padded to is determined.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaddingStrategy {
+    /// Segments are padded to `segment_padding_to_bytes`.
+    #[default]
+    Fixed {},
+    /// Segments are padded to `percent` percent more than the length of the largest segment of the
+    /// record, but at least to `min` bytes.
+    Percentage { percent: u8, min: u64 },
+}
+
+impl PaddingStrategy {
+    pub fn is_fixed(&self) -> bool {
+        matches!(self, Self::Fixed {})
+    }
+
+    fn min_padding_to_bytes(&self) -> Option<u64> {
+        match self {
+            Self::Fixed {} => None,
+            Self::Percentage { min, .. } => Some(*min),
+        }
+    }
+
+    /// Checks the parameters of the strategy, configured in `path`.
+    pub fn validate(&self, path: &Path) -> Result<(), Error> {
+        match self {
+            Self::Percentage { percent, .. } if *percent > 100 => {
+                Err(Error::InvalidPaddingStrategy {
+                    path: path.to_owned(),
+                    reason: "the padding percentage must not exceed 100",
+                })
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -188,6 +427,36 @@ pub enum SplittingStrategy {
     Fill {},
     /// Each segment is created from its corresponding numbered data file.
     Manual {},
+    /// The data is split at the given byte offsets, which must be strictly increasing and lie
+    /// within the data.
+    Explicit { offsets: Vec<u64> },
+}
+
+impl SplittingStrategy {
+    /// Checks the parameters of the strategy, configured in `path`, which can be checked without
+    /// knowing the data.
+    pub fn validate(&self, path: &Path) -> Result<(), Error> {
+        match self {
+            Self::Fill {} | Self::Manual {} => Ok(()),
+            Self::Explicit { offsets } => {
+                if offsets.is_empty() {
+                    return Err(Error::InvalidSplittingStrategy {
+                        path: path.to_owned(),
+                        reason: "the explicit strategy requires at least one offset",
+                    });
+                }
+
+                if offsets[0] == 0 || offsets.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err(Error::InvalidSplittingStrategy {
+                        path: path.to_owned(),
+                        reason: "the offsets of the explicit strategy must be positive and strictly increasing",
+                    });
+                }
+
+                Ok(())
+            }
+        }
+    }
 }
 
 impl Default for SplittingStrategy {
@@ -197,15 +466,59 @@ impl Default for SplittingStrategy {
 }
 
 /// Parameters can be defined in the registry config, and individually overwritten in each record config.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct OwnedRecordConfigParameters {
     pub splitting_strategy: SplittingStrategy,
     pub encryption: Option<OwnedRecordConfigEncryption>,
+    /// The line ending that the line endings in the data of text records are converted to.
+    /// The data of other records is left untouched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalize_line_endings: Option<LineEnding>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    /// Converts both `\r\n` and `\n` line endings in `data` to this line ending.
+    pub fn normalize(self, data: &[u8]) -> Vec<u8> {
+        let mut normalized = Vec::with_capacity(data.len());
+        let mut bytes = data.iter().copied().peekable();
+
+        while let Some(byte) = bytes.next() {
+            if byte == b'\r' && bytes.peek() == Some(&b'\n') {
+                continue;
+            }
+
+            if byte == b'\n' {
+                if self == Self::Crlf {
+                    normalized.push(b'\r');
+                }
+
+                normalized.push(b'\n');
+            } else {
+                normalized.push(byte);
+            }
+        }
+
+        normalized
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct OwnedRecordConfig {
     pub name: ByteBuf,
+    pub data_ref: Option<PathBuf>,
+    pub intermediate: Option<bool>,
+    pub draft: bool,
+    pub text: bool,
+    pub version_ignore_metadata: Vec<String>,
     pub metadata: OwnedRecordMetadata,
     pub parameters: OwnedRecordConfigParameters,
 }
@@ -219,71 +532,467 @@ pub struct OwnedRecordReadSuccess<R: AsyncRead + Unpin + Send + Sync> {
 pub struct OwnedRecord {
     pub directory_path: PathBuf,
     pub config: OwnedRecordConfig,
+    /// The resolved path of the file referenced by `config.data_ref`, if any.
+    pub data_ref_path: Option<PathBuf>,
+    /// Whether the record is declared in the `records.toml` manifest of its parent record, rather
+    /// than in a directory of its own. Such records share the `directory_path` of their parent, and
+    /// may only have data through `config.data_ref`.
+    pub declared_in_manifest: bool,
     pub successive_records: Vec<OwnedRecord>,
 }
 
+/// Declares the successive records of a record inline, instead of in subdirectories.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OwnedRecordManifest {
+    #[serde(default)]
+    pub records: Vec<OwnedRecordConfigUnresolved>,
+}
+
+impl OwnedRecordManifest {
+    /// Returns the keys of the manifest `value` which are not recognized, including those of the
+    /// declared records.
+    pub fn find_unknown_keys(value: &toml::Value) -> Result<Vec<String>> {
+        let mut unknown_keys = find_ignored_keys::<Self>(value.clone())?;
+
+        if let Some(records) = value.get("records").and_then(toml::Value::as_array) {
+            for (index, record) in records.iter().enumerate() {
+                unknown_keys.extend(
+                    OwnedRecordConfigUnresolved::find_unknown_keys(record)?
+                        .into_iter()
+                        .map(|key| format!("records.{index}.{key}")),
+                );
+            }
+        }
+
+        Ok(unknown_keys)
+    }
+}
+
+/// Options affecting how a source directory is loaded into a tree of [`OwnedRecord`]s.
+#[derive(Clone, Debug, Default)]
+pub struct OwnedRecordLoadOptions {
+    /// Whether symbolic links to directories should be followed when looking for successive records.
+    /// When disabled, such links are ignored. When enabled, links forming a cycle result in an error.
+    pub follow_symlinks: bool,
+    /// If set, used instead of the directory creation time as the creation time of records without
+    /// a config file, making the output reproducible.
+    pub source_date_epoch: Option<DateTime<Utc>>,
+    /// Whether the default record parameters of the registry config should be ignored, so that
+    /// every record has to specify its parameters, or inherit them from its parent record.
+    pub no_default_parameters: bool,
+    /// Whether record names derived from directory names should be canonicalized with
+    /// [`OwnedRecord::canonicalize_record_name`], so that they do not depend on how the platform
+    /// treats trailing dots, whitespace and Unicode normalization.
+    pub canonical_paths: bool,
+    /// Default record parameters of the user, see [`UserConfig`](crate::user_config::UserConfig),
+    /// used for those parameters which neither a record config nor the registry config specifies.
+    pub user_default_record_parameters: Option<OwnedRecordConfigParametersUnresolved>,
+    /// Whether indexed data files of a record with different extensions should be an error, rather
+    /// than only being warned about.
+    pub deny_inconsistent_data_extensions: bool,
+    /// Whether keys which are not recognized in record configs and manifests should be errors,
+    /// rather than being ignored.
+    pub deny_unknown_config_keys: bool,
+}
+
 impl OwnedRecord {
     pub fn load_from_directory<'a>(
+        registry_directory_path: &'a Path,
+        registry_config: &'a OwnedRegistryConfig,
+        parent_record_config: Option<&'a OwnedRecordConfig>,
+        directory_path: impl AsRef<Path> + Send + Sync + 'a,
+        options: &'a OwnedRecordLoadOptions,
+    ) -> BoxFuture<'a, Result<Self>> {
+        async move {
+            Self::load_from_directory_with_ancestors(
+                registry_directory_path,
+                registry_config,
+                parent_record_config,
+                directory_path,
+                options,
+                &mut Vec::new(),
+            )
+            .await
+        }
+        .boxed()
+    }
+
+    /// `ancestor_directories` contains the canonical paths of the directories of all ancestor records,
+    /// and is only tracked when following symbolic links.
+    fn load_from_directory_with_ancestors<'a>(
+        registry_directory_path: &'a Path,
         registry_config: &'a OwnedRegistryConfig,
         parent_record_config: Option<&'a OwnedRecordConfig>,
         directory_path: impl AsRef<Path> + Send + Sync + 'a,
+        options: &'a OwnedRecordLoadOptions,
+        ancestor_directories: &'a mut Vec<PathBuf>,
     ) -> BoxFuture<'a, Result<Self>> {
         async move {
+            if options.follow_symlinks {
+                let canonical_directory_path = tokio::fs::canonicalize(&directory_path).await?;
+
+                if ancestor_directories.contains(&canonical_directory_path) {
+                    return Err(Error::SymlinkCycle {
+                        path: directory_path.as_ref().to_owned(),
+                    }
+                    .into());
+                }
+
+                ancestor_directories.push(canonical_directory_path);
+            }
+
             // Resolve the incomplete record config.
             let config = {
-                let fallback_config_parameters = match parent_record_config {
-                    Some(parent_config)
-                        if registry_config.inherit_record_parameters_from_parent =>
-                    {
-                        parent_config.parameters.clone().into()
-                    } // TODO: cloning seems excessive
-                    _ => registry_config.default_record_parameters.clone(), // TODO: cloning seems excessive
-                };
-                let config_unresolved = Self::load_config(&directory_path).await?;
+                let fallback_config_parameters = Self::get_fallback_config_parameters(
+                    registry_config,
+                    parent_record_config,
+                    options,
+                );
+                let config_unresolved =
+                    Self::load_config(&directory_path, parent_record_config.is_none(), options)
+                        .await?;
 
                 config_unresolved
-                    .try_resolve_with(fallback_config_parameters)
-                    .map_err(|_| eyre!("incomplete record parameters"))?
+                    .try_resolve_with(fallback_config_parameters, directory_path.as_ref())?
+            };
+            let data_ref_path = match config.data_ref.as_ref() {
+                Some(data_ref) => Some(
+                    Self::resolve_data_ref(registry_directory_path, registry_config, data_ref)
+                        .await?,
+                ),
+                None => None,
             };
 
-            let mut successive_records_stream = tokio::fs::read_dir(&directory_path).await?;
             let mut successive_records = Vec::new();
-            let mut successive_record_names = HashSet::new();
 
-            while let Some(entry) = successive_records_stream.next_entry().await? {
-                if entry.metadata().await?.is_dir() {
-                    let successive_record_directory = entry.path();
-                    let successive_record = OwnedRecord::load_from_directory(
-                        registry_config,
-                        Some(&config),
-                        &successive_record_directory,
-                    )
-                    .await?;
-                    let successive_record_name_unique =
-                        successive_record_names.insert(successive_record.config.name.clone());
-
-                    if successive_record_name_unique {
-                        successive_records.push(successive_record);
-                    } else {
-                        return Err(Error::DuplicateSuccessiveRecord {
-                            parent: directory_path.as_ref().to_owned(),
-                            name: successive_record.config.name.to_vec(),
+            match tokio::fs::read_to_string(Self::get_manifest_path_from_record_directory_path(
+                &directory_path,
+            ))
+            .await
+            {
+                Ok(manifest_string) => {
+                    let mut entries = tokio::fs::read_dir(&directory_path).await?;
+
+                    while let Some(entry) = entries.next_entry().await? {
+                        if Self::is_successive_record_directory(&entry, options).await? {
+                            return Err(
+                                Error::SubdirectoryBesideManifest { path: entry.path() }.into()
+                            );
                         }
-                        .into());
+                    }
+
+                    if options.deny_unknown_config_keys {
+                        let manifest_value = toml::from_str::<toml::Value>(&manifest_string)?;
+
+                        ensure_no_unknown_keys(
+                            Self::get_manifest_path_from_record_directory_path(&directory_path),
+                            OwnedRecordManifest::find_unknown_keys(&manifest_value)?,
+                        )?;
+                    }
+
+                    let manifest = toml::from_str::<OwnedRecordManifest>(&manifest_string)?;
+
+                    for successive_config_unresolved in manifest.records {
+                        successive_records.push(
+                            Self::load_from_manifest_entry(
+                                registry_directory_path,
+                                registry_config,
+                                &config,
+                                &directory_path,
+                                successive_config_unresolved,
+                                options,
+                            )
+                            .await?,
+                        );
                     }
                 }
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                    let mut successive_records_stream =
+                        tokio::fs::read_dir(&directory_path).await?;
+
+                    while let Some(entry) = successive_records_stream.next_entry().await? {
+                        if Self::is_successive_record_directory(&entry, options).await? {
+                            successive_records.push(
+                                OwnedRecord::load_from_directory_with_ancestors(
+                                    registry_directory_path,
+                                    registry_config,
+                                    Some(&config),
+                                    entry.path(),
+                                    options,
+                                    ancestor_directories,
+                                )
+                                .await?,
+                            );
+                        }
+                    }
+                }
+                Err(error) => return Err(error.into()),
+            }
+
+            let mut successive_record_names = HashSet::new();
+
+            for successive_record in &successive_records {
+                if !successive_record_names.insert(&successive_record.config.name) {
+                    return Err(Error::DuplicateSuccessiveRecord {
+                        parent: directory_path.as_ref().to_owned(),
+                        name: successive_record.config.name.to_vec(),
+                    }
+                    .into());
+                }
+            }
+
+            if options.follow_symlinks {
+                ancestor_directories.pop();
             }
 
-            Ok(Self {
+            let record = Self {
                 directory_path: directory_path.as_ref().to_owned(),
                 config,
+                data_ref_path,
+                declared_in_manifest: false,
                 successive_records,
-            })
+            };
+
+            let data_paths = record.get_data_paths().await?;
+
+            record.validate_intermediate(&data_paths)?;
+            record
+                .validate_data_extensions(&data_paths, options.deny_inconsistent_data_extensions)?;
+
+            Ok(record)
         }
         .boxed()
     }
 
+    /// Whether `entry` is a directory of a successive record, rather than a file of the record.
+    async fn is_successive_record_directory(
+        entry: &tokio::fs::DirEntry,
+        options: &OwnedRecordLoadOptions,
+    ) -> Result<bool> {
+        // `DirEntry::file_type` does not follow symbolic links.
+        let file_type = entry.file_type().await?;
+
+        Ok(if file_type.is_symlink() {
+            options.follow_symlinks && tokio::fs::metadata(entry.path()).await?.is_dir()
+        } else {
+            file_type.is_dir()
+        })
+    }
+
+    /// Loads a successive record of the record in `directory_path`, declared in its manifest.
+    async fn load_from_manifest_entry(
+        registry_directory_path: &Path,
+        registry_config: &OwnedRegistryConfig,
+        parent_record_config: &OwnedRecordConfig,
+        directory_path: impl AsRef<Path>,
+        config_unresolved: OwnedRecordConfigUnresolved,
+        options: &OwnedRecordLoadOptions,
+    ) -> Result<Self> {
+        let fallback_config_parameters = Self::get_fallback_config_parameters(
+            registry_config,
+            Some(parent_record_config),
+            options,
+        );
+        let config = config_unresolved
+            .try_resolve_with(fallback_config_parameters, directory_path.as_ref())?;
+        let data_ref_path = match config.data_ref.as_ref() {
+            Some(data_ref) => Some(
+                Self::resolve_data_ref(registry_directory_path, registry_config, data_ref).await?,
+            ),
+            None => None,
+        };
+        let record = Self {
+            directory_path: directory_path.as_ref().to_owned(),
+            config,
+            data_ref_path,
+            declared_in_manifest: true,
+            successive_records: Vec::new(),
+        };
+
+        record.validate_intermediate(&record.get_data_paths().await?)?;
+
+        Ok(record)
+    }
+
+    /// Returns the parameters used for those parameters which a record config does not specify.
+    fn get_fallback_config_parameters(
+        registry_config: &OwnedRegistryConfig,
+        parent_record_config: Option<&OwnedRecordConfig>,
+        options: &OwnedRecordLoadOptions,
+    ) -> OwnedRecordConfigParametersUnresolved {
+        match parent_record_config {
+            Some(parent_config) if registry_config.inherit_record_parameters_from_parent => {
+                parent_config.parameters.clone().into()
+            } // TODO: cloning seems excessive
+            _ if options.no_default_parameters => Default::default(),
+            _ => {
+                // TODO: cloning seems excessive
+                let default_record_parameters = registry_config.default_record_parameters.clone();
+
+                match options.user_default_record_parameters.as_ref() {
+                    Some(user_default_record_parameters) => {
+                        default_record_parameters.or(user_default_record_parameters.clone())
+                    }
+                    None => default_record_parameters,
+                }
+            }
+        }
+    }
+
+    /// Whether the record has neither data nor successive records.
+    pub async fn is_empty(&self) -> Result<bool> {
+        Ok(self.successive_records.is_empty() && self.get_data_paths().await?.is_empty())
+    }
+
+    /// Counts this record and all of its successive records, recursively.
+    pub fn count_records(&self) -> usize {
+        1 + self
+            .successive_records
+            .iter()
+            .map(OwnedRecord::count_records)
+            .sum::<usize>()
+    }
+
+    /// Iterates over this record and all of its successive records, depth-first, in the order in
+    /// which [`make_recursive`](crate::make_recursive) builds them. Each record is yielded along
+    /// with its path, starting with the name of this record. Record names are used verbatim, so
+    /// the paths differ from those of a build using a
+    /// [`RecordNameTransform`](crate::name_transform::RecordNameTransform).
+    pub fn iter(&self) -> impl Iterator<Item = (RecordPath, &OwnedRecord)> + '_ {
+        let mut stack = vec![(vec![RecordName::from(self.config.name.to_vec())], self)];
+
+        std::iter::from_fn(move || {
+            let (record_names, record) = stack.pop()?;
+
+            // Pushed in reverse, so that successive records are yielded in order.
+            for successive_record in record.successive_records.iter().rev() {
+                let mut successive_record_names = record_names.clone();
+                successive_record_names
+                    .push(RecordName::from(successive_record.config.name.to_vec()));
+                stack.push((successive_record_names, successive_record));
+            }
+
+            Some((RecordPath::try_from(record_names).unwrap(), record))
+        })
+    }
+
+    /// Ensures that all indexed data files among `data_paths` share the same extension, as a mix
+    /// such as `data.0.bin` and `data.1.txt` is most likely a mistake. Unless `deny`, a warning is
+    /// logged instead.
+    fn validate_data_extensions(&self, data_paths: &[PathBuf], deny: bool) -> Result<()> {
+        let mut extensions = Vec::<String>::new();
+
+        for data_path in data_paths {
+            let Some(Some(_)) = data_path.file_name().and_then(Self::parse_data_file_name) else {
+                continue;
+            };
+            // The file name has the format `data.[index].[ext]`.
+            let file_name = data_path.file_name().unwrap().to_string_lossy();
+            let extension = file_name.splitn(3, '.').nth(2).unwrap_or_default();
+
+            if !extensions.iter().any(|other| other == extension) {
+                extensions.push(extension.to_owned());
+            }
+        }
+
+        if extensions.len() > 1 {
+            if deny {
+                return Err(Error::InconsistentDataExtension {
+                    path: self.directory_path.clone(),
+                    extensions,
+                }
+                .into());
+            }
+
+            warn!(
+                directory = ?self.directory_path,
+                ?extensions,
+                "Indexed data files have different extensions."
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Ensures that the presence of data, as found in `data_paths`, matches the `intermediate` field
+    /// of the config, if set.
+    fn validate_intermediate(&self, data_paths: &[PathBuf]) -> Result<()> {
+        let Some(intermediate) = self.config.intermediate else {
+            return Ok(());
+        };
+        let has_data = !data_paths.is_empty();
+
+        if intermediate && has_data {
+            return Err(Error::IntermediateRecordHasData {
+                directory: self.directory_path.clone(),
+            }
+            .into());
+        }
+
+        if !intermediate && !has_data {
+            return Err(Error::RecordDataMissing {
+                directory: self.directory_path.clone(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Resolves `data_ref` relative to the registry directory, ensuring it points to an existing file
+    /// within the registry directory, which is none of the paths reserved by the registry config.
+    async fn resolve_data_ref(
+        registry_directory_path: &Path,
+        registry_config: &OwnedRegistryConfig,
+        data_ref: &Path,
+    ) -> Result<PathBuf> {
+        let invalid_data_ref = || Error::InvalidDataRef {
+            data_ref: data_ref.to_owned(),
+        };
+        let registry_directory_path = tokio::fs::canonicalize(registry_directory_path).await?;
+        let data_ref_path =
+            match tokio::fs::canonicalize(registry_directory_path.join(data_ref)).await {
+                Ok(data_ref_path) => data_ref_path,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(invalid_data_ref().into())
+                }
+                Err(error) => return Err(error.into()),
+            };
+
+        if !data_ref_path.starts_with(&registry_directory_path)
+            || !tokio::fs::metadata(&data_ref_path).await?.is_file()
+        {
+            return Err(invalid_data_ref().into());
+        }
+
+        for reserved_path in registry_config.get_reserved_paths(&registry_directory_path) {
+            let reserved_path = match tokio::fs::canonicalize(&reserved_path).await {
+                Ok(reserved_path) => reserved_path,
+                // Nothing can be referenced within a path which does not exist.
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+
+            if data_ref_path.starts_with(&reserved_path) {
+                return Err(Error::ReservedDataRef {
+                    data_ref: data_ref.to_owned(),
+                }
+                .into());
+            }
+        }
+
+        Ok(data_ref_path)
+    }
+
     pub async fn save(&self) -> Result<()> {
+        if self.declared_in_manifest {
+            // The directory belongs to the parent record.
+            bail!(
+                "records declared in a manifest cannot be saved to their own config: {:?}",
+                self.directory_path
+            );
+        }
+
         tokio::fs::create_dir_all(&self.directory_path).await?;
 
         let config_string =
@@ -299,8 +1008,12 @@ impl OwnedRecord {
         Ok(())
     }
 
+    /// Loads the record config in `directory_path`, or derives one from the directory if there is
+    /// none. `is_root` exempts the root record from the validation of the derived record name.
     pub async fn load_config(
         directory_path: impl AsRef<Path>,
+        is_root: bool,
+        options: &OwnedRecordLoadOptions,
     ) -> Result<OwnedRecordConfigUnresolved> {
         match tokio::fs::read_to_string(Self::get_config_path_from_record_directory_path(
             &directory_path,
@@ -308,6 +1021,15 @@ impl OwnedRecord {
         .await
         {
             Ok(config_string) => {
+                if options.deny_unknown_config_keys {
+                    let config_value = toml::from_str::<toml::Value>(&config_string)?;
+
+                    ensure_no_unknown_keys(
+                        Self::get_config_path_from_record_directory_path(&directory_path),
+                        OwnedRecordConfigUnresolved::find_unknown_keys(&config_value)?,
+                    )?;
+                }
+
                 toml::from_str::<OwnedRecordConfigUnresolved>(&config_string).map_err(Into::into)
             }
             Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
@@ -326,12 +1048,47 @@ impl OwnedRecord {
                         format!("Cannot derive a record name from the path segment {file_name:?}, as it is not a valid UTF-8 string.")
                     )
                 })?;
-                let created_at_system = tokio::fs::metadata(&directory_path).await?.created()?;
-                let created_at_chrono = DateTime::<Utc>::from(created_at_system);
+
+                let file_name_utf8 = if options.canonical_paths {
+                    let canonical_name = Self::canonicalize_record_name(file_name_utf8);
+
+                    if canonical_name != file_name_utf8 {
+                        warn!(
+                            directory = ?directory_path.as_ref(),
+                            %canonical_name,
+                            "Record name derived from the directory name was canonicalized."
+                        );
+                    }
+
+                    Cow::Owned(canonical_name)
+                } else {
+                    Cow::Borrowed(file_name_utf8)
+                };
+
+                if !is_root && file_name_utf8.trim().is_empty() {
+                    return Err(Error::BlankRecordName {
+                        directory: directory_path.as_ref().to_owned(),
+                    }
+                    .into());
+                }
+
+                let created_at_chrono = match options.source_date_epoch {
+                    Some(source_date_epoch) => source_date_epoch,
+                    None => {
+                        let created_at_system =
+                            tokio::fs::metadata(&directory_path).await?.created()?;
+                        DateTime::<Utc>::from(created_at_system)
+                    }
+                };
                 let created_at =
                     toml::value::Datetime::from_str(&created_at_chrono.to_rfc3339()).unwrap();
                 Ok(OwnedRecordConfigUnresolved {
                     name: ByteBuf::from(file_name_utf8.as_bytes()),
+                    data_ref: None,
+                    intermediate: None,
+                    draft: false,
+                    text: false,
+                    version_ignore_metadata: Vec::new(),
                     metadata: OwnedRecordMetadata {
                         created_at: Some(created_at),
                     },
@@ -342,6 +1099,13 @@ impl OwnedRecord {
         }
     }
 
+    /// Strips leading and trailing whitespace and trailing dots from a directory-derived record name,
+    /// which some platforms strip from directory names, and normalizes it to Unicode Normalization
+    /// Form C, as some platforms store directory names decomposed.
+    pub fn canonicalize_record_name(name: &str) -> String {
+        name.trim().trim_end_matches('.').trim_end().nfc().collect()
+    }
+
     pub async fn read(
         &self,
     ) -> Result<Option<OwnedRecordReadSuccess<impl AsyncRead + Unpin + Send + Sync>>> {
@@ -351,7 +1115,7 @@ impl OwnedRecord {
         };
         let mut split_at = match self.config.parameters.splitting_strategy {
             SplittingStrategy::Fill {} => None,
-            SplittingStrategy::Manual {} => Some(Vec::new()),
+            SplittingStrategy::Manual {} | SplittingStrategy::Explicit { .. } => Some(Vec::new()),
         };
         let mut read: Box<dyn AsyncRead + Unpin + Send + Sync> = {
             let file_first = tokio::fs::OpenOptions::new()
@@ -377,24 +1141,114 @@ impl OwnedRecord {
             read = Box::new(read.chain(file));
         }
 
-        if let Some(split_at) = split_at.as_mut() {
+        if let SplittingStrategy::Explicit { offsets } = &self.config.parameters.splitting_strategy
+        {
+            let data_length = split_at.iter().flatten().sum::<usize>() as u64;
+
+            split_at = Some(self.explicit_segment_lengths(offsets, data_length)?);
+        } else if let Some(split_at) = split_at.as_mut() {
             split_at.pop();
         }
 
         Ok(Some(OwnedRecordReadSuccess { read, split_at }))
     }
 
+    /// Converts the absolute split `offsets` into the lengths of the leading segments, as expected
+    /// by `split_at`.
+    fn explicit_segment_lengths(&self, offsets: &[u64], data_length: u64) -> Result<Vec<usize>> {
+        let mut segment_lengths = Vec::with_capacity(offsets.len());
+        let mut previous_offset = 0;
+
+        for &offset in offsets {
+            if offset <= previous_offset || offset >= data_length {
+                return Err(Error::InvalidSplitOffsets {
+                    directory: self.directory_path.clone(),
+                    offsets: offsets.to_vec(),
+                    data_length,
+                }
+                .into());
+            }
+
+            segment_lengths.push((offset - previous_offset) as usize);
+            previous_offset = offset;
+        }
+
+        Ok(segment_lengths)
+    }
+
     pub fn get_config_path_from_record_directory_path(directory_path: impl AsRef<Path>) -> PathBuf {
         directory_path.as_ref().join("record.toml")
     }
 
+    pub fn get_manifest_path_from_record_directory_path(
+        directory_path: impl AsRef<Path>,
+    ) -> PathBuf {
+        directory_path.as_ref().join("records.toml")
+    }
+
+    /// The path of the file declaring the record's config, which is the manifest of the parent
+    /// record for records declared in one.
     pub fn get_config_path(&self) -> PathBuf {
-        Self::get_config_path_from_record_directory_path(&self.directory_path)
+        if self.declared_in_manifest {
+            Self::get_manifest_path_from_record_directory_path(&self.directory_path)
+        } else {
+            Self::get_config_path_from_record_directory_path(&self.directory_path)
+        }
     }
 
-    pub async fn get_data_paths(&self) -> Result<Vec<PathBuf>> {
+    /// Whether the file declaring the record's config, or any of its data files, was modified after
+    /// `time`.
+    pub async fn modified_since(&self, time: SystemTime) -> Result<bool> {
+        for path in std::iter::once(self.get_config_path()).chain(self.get_data_paths().await?) {
+            match tokio::fs::metadata(&path).await {
+                Ok(metadata) => {
+                    if metadata.modified()? > time {
+                        return Ok(true);
+                    }
+                }
+                // Records without a config file derive their config from the directory.
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns `None` if `file_name` is not the name of a data file. Otherwise, returns the index of
+    /// the data file, if it is indexed, i.e. its name has the format `data.[index].[ext]`.
+    pub fn parse_data_file_name(file_name: &OsStr) -> Option<Option<usize>> {
         const FILE_STEM_DATA: &[u8] = b"data";
 
+        let mut iter = file_name.as_encoded_bytes().splitn(2, |byte| *byte == b'.');
+        let stem_bytes = iter.next().expect("expected a non-empty file name");
+
+        if stem_bytes != FILE_STEM_DATA {
+            return None;
+        }
+
+        if let Some(extensions_bytes) = iter.next() {
+            let mut iter = extensions_bytes.splitn(2, |byte| *byte == b'.');
+            let first = iter.next().unwrap();
+
+            if iter.next().is_some() {
+                if let Ok(first) = str::from_utf8(first) {
+                    if let Ok(index) = first.parse::<usize>() {
+                        return Some(Some(index));
+                    }
+                }
+            }
+        }
+
+        Some(None)
+    }
+
+    pub async fn get_data_paths(&self) -> Result<Vec<PathBuf>> {
+        // The directory belongs to the parent record.
+        if self.declared_in_manifest {
+            return Ok(self.data_ref_path.iter().cloned().collect());
+        }
+
         let mut read_dir = tokio::fs::read_dir(&self.directory_path).await?;
         let mut results = Vec::<(Option<usize>, PathBuf)>::new();
 
@@ -402,29 +1256,19 @@ impl OwnedRecord {
             if dir_entry.file_type().await?.is_file() {
                 let path = dir_entry.path();
                 let file_name = path.file_name().expect("regular file expected");
-                let mut iter = file_name.as_encoded_bytes().splitn(2, |byte| *byte == b'.');
-                let stem_bytes = iter.next().expect("expected a non-empty file name");
 
-                if stem_bytes != FILE_STEM_DATA {
-                    continue;
-                }
-
-                if let Some(extensions_bytes) = iter.next() {
-                    let mut iter = extensions_bytes.splitn(2, |byte| *byte == b'.');
-                    let first = iter.next().unwrap();
-
-                    if iter.next().is_some() {
-                        if let Ok(first) = str::from_utf8(first) {
-                            if let Ok(index) = first.parse::<usize>() {
-                                results.push((Some(index), path));
-                                continue;
-                            }
-                        }
-                    }
+                if let Some(index) = Self::parse_data_file_name(file_name) {
+                    results.push((index, path));
                 }
+            }
+        }
 
-                results.push((None, path));
+        if let Some(data_ref_path) = self.data_ref_path.as_ref() {
+            if let Some((_, path)) = results.first() {
+                bail!("record data files must not be present when `data_ref` is used: {path:?}");
             }
+
+            return Ok(vec![data_ref_path.clone()]);
         }
 
         if results.is_empty() {
@@ -468,3 +1312,53 @@ impl OwnedRecord {
         Ok(results.into_iter().map(|(_, path)| path).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalize_record_name() {
+        // "e" followed by a combining acute accent, as stored by some file systems.
+        assert_eq!(
+            OwnedRecord::canonicalize_record_name(" cafe\u{301}. "),
+            "caf\u{e9}"
+        );
+        assert_eq!(
+            OwnedRecord::canonicalize_record_name("caf\u{e9}"),
+            "caf\u{e9}"
+        );
+    }
+
+    #[test]
+    fn line_ending_normalize() {
+        // A lone `\r` is not a line ending.
+        let data = b"First\r\nSecond\nThird\rFourth";
+
+        assert_eq!(
+            LineEnding::Lf.normalize(data),
+            b"First\nSecond\nThird\rFourth"
+        );
+        assert_eq!(
+            LineEnding::Crlf.normalize(data),
+            b"First\r\nSecond\r\nThird\rFourth"
+        );
+    }
+
+    #[test]
+    fn percentage_padding() {
+        let encryption = OwnedRecordConfigEncryption {
+            algorithm: EncryptionAlgorithm::Aes256Gcm,
+            segment_padding_to_bytes: 256,
+            padding_strategy: PaddingStrategy::Percentage {
+                percent: 10,
+                min: 256,
+            },
+        };
+
+        assert_eq!(encryption.segment_padding_to_bytes_for(0), 256);
+        assert_eq!(encryption.segment_padding_to_bytes_for(100), 256);
+        assert_eq!(encryption.segment_padding_to_bytes_for(10_000), 11_000);
+        assert_eq!(encryption.segment_padding_to_bytes_for(20_001), 22_002);
+    }
+}
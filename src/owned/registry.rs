@@ -1,13 +1,17 @@
 use aes_gcm::aead::OsRng;
-use color_eyre::Result;
-use ed25519_dalek::pkcs8::{spki::der::pem::LineEnding, DecodePrivateKey, EncodePrivateKey};
+use color_eyre::{eyre::bail, Result};
+use ed25519_dalek::pkcs8::{
+    spki::der::pem::LineEnding, DecodePrivateKey, EncodePrivateKey, EncodePublicKey,
+};
 use itertools::Itertools;
 use rrr::crypto::signature::{SigningKey, SigningKeyEd25519};
-use rrr::record::RecordKey;
-use rrr::registry::{RegistryConfig, RegistryConfigHash, RegistryConfigKdf};
+use rrr::record::{HashedRecordKey, RecordKey, RecordName};
+use rrr::registry::{Registry, RegistryConfig, RegistryConfigHash, RegistryConfigKdf};
 use rrr::utils::fd_lock::{FileLock, FileLockType, ReadLock, WriteLock};
 use rrr::utils::serde::Secret;
 use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::io::SeekFrom;
 use std::{
@@ -22,10 +26,15 @@ use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
 };
 use toml_edit::DocumentMut;
+use tracing::warn;
+use zeroize::Zeroizing;
 
-use crate::assets;
 use crate::error::Error;
-use crate::record::OwnedRecordConfigParametersUnresolved;
+use crate::record::{OwnedRecordConfigParametersUnresolved, OwnedRecordLoadOptions};
+use crate::secrets::{EncryptedSecrets, Secrets};
+use crate::spec::RecordSpec;
+use crate::util::serde::{ensure_no_unknown_keys, find_ignored_keys};
+use crate::{assets, util};
 
 use super::record::OwnedRecord;
 
@@ -39,6 +48,11 @@ pub struct OwnedRegistryConfig {
     pub inherit_record_parameters_from_parent: bool,
     pub default_record_parameters: OwnedRecordConfigParametersUnresolved,
     pub root_record_path: PathBuf,
+    /// The name of the root record, which readers of the registry need to know to find it.
+    /// When set, it takes precedence over the name in the root record's config.
+    /// When omitted, the root record name is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_record_name: Option<ByteBuf>,
     /// This is where the resulting registry is generated, every time the `make` subcommand is executed.
     pub staging_directory_path: PathBuf,
     /// This directory contains all of the published record fragments, separated to directories according
@@ -50,30 +64,209 @@ pub struct OwnedRegistryConfig {
     /// Paths to files with signing keys.
     /// These paths are relative to the directory containing the registry config.
     pub signing_key_paths: Vec<PathBuf>,
+    /// Which records must be encrypted, checked when building the records.
+    #[serde(default)]
+    pub require_encryption: RequireEncryption,
+    /// Whether a registry whose root record has neither data nor successive records may be built.
+    /// When disabled, building such a registry fails, so that it is not published accidentally.
+    #[serde(default)]
+    pub allow_empty: bool,
+    /// Secrets encrypted with the master passphrase of the registry, see [`OwnedRegistry::secrets`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<EncryptedSecrets>,
+}
+
+/// A policy on which records must be encrypted.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RequireEncryption {
+    /// Records need not be encrypted.
+    #[default]
+    None,
+    /// All records except for the root record must be encrypted.
+    AllExceptRoot,
+    /// All records, including the root record, must be encrypted.
+    All,
+}
+
+impl RequireEncryption {
+    pub fn is_required(&self, is_root: bool) -> bool {
+        match self {
+            Self::None => false,
+            Self::AllExceptRoot => !is_root,
+            Self::All => true,
+        }
+    }
 }
 
 impl OwnedRegistryConfig {
+    /// Checks the parts of the config, read from `config_path`, which are not already checked by
+    /// its deserialization.
+    pub fn validate(&self, config_path: &Path) -> Result<()> {
+        if self.signing_key_paths.is_empty() {
+            bail!("the registry config must list at least one signing key");
+        }
+
+        if let Some(splitting_strategy) = self.default_record_parameters.splitting_strategy.as_ref()
+        {
+            splitting_strategy.validate(config_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Paths within the registry directory at `directory_path` which records must not reference
+    /// with `data_ref`, as their contents must not be published: the registry config, the signing
+    /// keys and the output directories.
+    pub(crate) fn get_reserved_paths(&self, directory_path: impl AsRef<Path>) -> Vec<PathBuf> {
+        let directory_path = directory_path.as_ref();
+
+        std::iter::once(
+            OwnedRegistry::<ReadLock>::get_config_path_from_registry_directory_path(directory_path),
+        )
+        .chain(
+            self.signing_key_paths
+                .iter()
+                .chain([
+                    &self.staging_directory_path,
+                    &self.revisions_directory_path,
+                    &self.published_directory_path,
+                ])
+                .map(|path| directory_path.join(path)),
+        )
+        .collect()
+    }
+
     pub fn get_root_record_key(&self) -> RecordKey {
         RecordKey {
-            record_name: Default::default(),
+            record_name: self
+                .root_record_name
+                .as_ref()
+                .map(|root_record_name| RecordName::from(root_record_name.to_vec()))
+                .unwrap_or_default(),
             predecessor_nonce: self.kdf.get_root_record_predecessor_nonce().clone(),
         }
     }
+
+    /// Derives the hashed key of the record at the path `record_names`, the first element of which
+    /// is the name of the root record.
+    pub async fn hash_record_path(&self, record_names: &[RecordName]) -> Result<HashedRecordKey> {
+        let Some((root_record_name, successive_record_names)) = record_names.split_first() else {
+            bail!("cannot hash an empty record path");
+        };
+        let mut hashed_key = RecordKey {
+            record_name: root_record_name.clone(),
+            predecessor_nonce: self.kdf.get_root_record_predecessor_nonce().clone(),
+        }
+        .hash(&self.hash)
+        .await?;
+
+        for record_name in successive_record_names {
+            let predecessor_nonce = hashed_key.derive_succession_nonce(&self.kdf).await?;
+
+            hashed_key = RecordKey {
+                record_name: record_name.clone(),
+                predecessor_nonce,
+            }
+            .hash(&self.hash)
+            .await?;
+        }
+
+        Ok(hashed_key)
+    }
+}
+
+/// Options affecting how a registry is loaded by [`OwnedRegistry::load_with_options`].
+#[derive(Clone, Default)]
+pub struct OwnedRegistryLoadOptions {
+    /// Whether keys which are not recognized in the registry config should be errors, rather than
+    /// being ignored.
+    pub deny_unknown_config_keys: bool,
+    /// The master passphrase to decrypt the secrets in the registry config with, see
+    /// [`OwnedRegistry::secrets`].
+    pub secrets_passphrase: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl Debug for OwnedRegistryLoadOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedRegistryLoadOptions")
+            .field("deny_unknown_config_keys", &self.deny_unknown_config_keys)
+            .field(
+                "secrets_passphrase",
+                &self.secrets_passphrase.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// The decrypted secrets of a registry, along with the passphrase they are encrypted with again
+/// when the config is saved.
+#[derive(PartialEq, Eq)]
+struct UnlockedSecrets {
+    passphrase: Zeroizing<Vec<u8>>,
+    secrets: Secrets,
+}
+
+impl Debug for UnlockedSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.secrets.keys()).finish()
+    }
 }
 
 #[derive(Debug, Eq)]
 pub struct OwnedRegistry<L: FileLock> {
     pub directory_path: PathBuf,
     pub config: OwnedRegistryConfig,
-    /// Keys loaded from files at `config.signing_key_paths`, in the same order.
+    /// Keys loaded from files at `config.signing_key_paths`, in the same order, without duplicates.
     pub signing_keys: Vec<SigningKey>,
+    unlocked_secrets: Option<UnlockedSecrets>,
+    /// Whether the registry was loaded with
+    /// [`OwnedRegistryLoadOptions::deny_unknown_config_keys`], which [`Self::reload`] preserves.
+    deny_unknown_config_keys: bool,
     file_lock: L,
 }
 
 impl<L: FileLock> OwnedRegistry<L> {
     const FILE_NAME_CONFIG: &str = "registry.toml";
 
+    pub fn signing_key_count(&self) -> usize {
+        self.signing_keys.len()
+    }
+
+    /// The type names of the signing keys, in the order of `signing_key_paths`.
+    pub fn signing_key_types(&self) -> Vec<&'static str> {
+        self.signing_keys
+            .iter()
+            .map(|signing_key| signing_key.key_type_name())
+            .collect()
+    }
+
+    /// Hex-encoded SHA-256 digests of the DER-encoded SubjectPublicKeyInfo of the verifying keys,
+    /// in the order of `signing_key_paths`. These match the fingerprints computed by other tools,
+    /// e.g. `openssl pkey -pubout -outform DER | sha256sum`.
+    pub fn verifying_key_fingerprints(&self) -> Vec<String> {
+        self.signing_keys
+            .iter()
+            .map(|signing_key| {
+                let public_key_der = match signing_key {
+                    SigningKey::Ed25519(signing_key) => signing_key.0 .0.verifying_key(),
+                }
+                .to_public_key_der()
+                .expect("verifying keys should be encodable");
+
+                format!("{:x}", Sha256::digest(public_key_der.as_bytes()))
+            })
+            .collect()
+    }
+
     pub async fn load(directory_path: impl Into<PathBuf>) -> Result<Self> {
+        Self::load_with_options(directory_path, &Default::default()).await
+    }
+
+    pub async fn load_with_options(
+        directory_path: impl Into<PathBuf>,
+        options: &OwnedRegistryLoadOptions,
+    ) -> Result<Self> {
         let directory_path = directory_path.into();
         let config_path = Self::get_config_path_from_registry_directory_path(&directory_path);
         let open_options = {
@@ -83,6 +276,91 @@ impl<L: FileLock> OwnedRegistry<L> {
             open_options
         };
         let mut file_lock = L::lock(&config_path, &open_options).await?;
+        let (config, signing_keys) =
+            Self::read_config_and_signing_keys(&directory_path, &mut file_lock, options).await?;
+        let mut registry = Self {
+            config,
+            directory_path,
+            signing_keys,
+            unlocked_secrets: None,
+            deny_unknown_config_keys: options.deny_unknown_config_keys,
+            file_lock,
+        };
+
+        if let Some(passphrase) = options.secrets_passphrase.as_ref() {
+            registry.unlock_secrets(passphrase).await?;
+        }
+
+        Ok(registry)
+    }
+
+    /// Re-reads the config and signing keys from disk, while keeping the lock on the config.
+    /// The config is validated as strictly as when the registry was loaded, and unlocked secrets
+    /// are decrypted again with the same passphrase.
+    /// If the new config or keys are invalid, an error is returned and the registry is left as is.
+    ///
+    /// Only changes written into the locked config file are seen. A config replaced by renaming
+    /// another file over it is rejected with an error on Unix, and not detected elsewhere.
+    pub async fn reload(&mut self) -> Result<()> {
+        self.ensure_config_not_replaced().await?;
+        self.file_lock.file_mut().seek(SeekFrom::Start(0)).await?;
+
+        let options = OwnedRegistryLoadOptions {
+            deny_unknown_config_keys: self.deny_unknown_config_keys,
+            ..Default::default()
+        };
+        let (config, signing_keys) =
+            Self::read_config_and_signing_keys(&self.directory_path, &mut self.file_lock, &options)
+                .await?;
+
+        let unlocked_secrets = match self.unlocked_secrets.as_ref() {
+            Some(unlocked_secrets) => Some(UnlockedSecrets {
+                secrets: Self::decrypt_secrets(&config, &unlocked_secrets.passphrase).await?,
+                passphrase: unlocked_secrets.passphrase.clone(),
+            }),
+            None => None,
+        };
+
+        self.config = config;
+        self.signing_keys = signing_keys;
+        self.unlocked_secrets = unlocked_secrets;
+
+        Ok(())
+    }
+
+    /// Fails if the file at the config path is no longer the locked config file, which would
+    /// then only hold a stale config.
+    async fn ensure_config_not_replaced(&mut self) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let config_path = self.get_config_path();
+            let locked_metadata = self.file_lock.file_mut().metadata().await?;
+            let replaced = match tokio::fs::metadata(&config_path).await {
+                Ok(metadata) => {
+                    (metadata.dev(), metadata.ino())
+                        != (locked_metadata.dev(), locked_metadata.ino())
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => true,
+                Err(error) => return Err(error.into()),
+            };
+
+            if replaced {
+                return Err(Error::ConfigReplaced { path: config_path }.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads and validates the config from the locked config file, and loads the signing keys
+    /// it refers to.
+    async fn read_config_and_signing_keys(
+        directory_path: &Path,
+        file_lock: &mut L,
+        options: &OwnedRegistryLoadOptions,
+    ) -> Result<(OwnedRegistryConfig, Vec<SigningKey>)> {
         let config_string = {
             let mut config_string = String::new();
             file_lock
@@ -91,35 +369,124 @@ impl<L: FileLock> OwnedRegistry<L> {
                 .await?;
             config_string
         };
+
+        if options.deny_unknown_config_keys {
+            let config_value = toml::from_str::<toml::Value>(&config_string)?;
+            let mut unknown_keys = find_ignored_keys::<OwnedRegistryConfig>(config_value.clone())?;
+
+            if let Some(parameters_value) = config_value.get("default_record_parameters") {
+                unknown_keys.extend(
+                    OwnedRecordConfigParametersUnresolved::find_unknown_encryption_keys(
+                        parameters_value,
+                    )?
+                    .into_iter()
+                    .map(|key| format!("default_record_parameters.{key}")),
+                );
+            }
+
+            ensure_no_unknown_keys(
+                Self::get_config_path_from_registry_directory_path(directory_path),
+                unknown_keys,
+            )?;
+        }
+
         let config = toml::from_str::<OwnedRegistryConfig>(&config_string)?;
+
+        config.validate(&Self::get_config_path_from_registry_directory_path(
+            directory_path,
+        ))?;
+
         let signing_keys = {
             let mut signing_keys = Vec::new();
+            // Re-encoded keys, to detect distinct files containing the same key.
+            let mut signing_key_pems = Vec::new();
 
             for key_path in &config.signing_key_paths {
                 let key_path =
-                    Self::get_key_path_from_record_directory_path(&directory_path, key_path);
+                    Self::get_key_path_from_record_directory_path(directory_path, key_path);
                 let mut file = File::open(&key_path).await?;
                 let mut key_bytes = Default::default();
 
                 file.read_to_string(&mut key_bytes).await?;
 
-                let key = SigningKey::from_pkcs8_pem(&key_bytes).unwrap();
+                let (key, key_pem) = SigningKey::from_pkcs8_pem(&key_bytes)
+                    .ok()
+                    .and_then(|key| {
+                        let key_pem = key.to_pkcs8_pem(LineEnding::default()).ok()?;
+                        Some((key, key_pem))
+                    })
+                    .ok_or_else(|| Error::MalformedSigningKey {
+                        path: key_path.clone(),
+                    })?;
+
+                if signing_key_pems.contains(&key_pem) {
+                    warn!(
+                        ?key_path,
+                        "Ignoring a signing key identical to a previously loaded one."
+                    );
+                    continue;
+                }
 
+                signing_key_pems.push(key_pem);
                 signing_keys.push(key);
             }
 
             signing_keys
         };
 
-        Ok(Self {
-            config,
-            directory_path,
-            signing_keys,
-            file_lock,
-        })
+        Ok((config, signing_keys))
+    }
+
+    /// Decrypts the secrets stored in the registry config with the master `passphrase`, making
+    /// them available through [`Self::secrets`] and [`Self::secrets_mut`]. Afterwards, saving the
+    /// config encrypts the secrets with the same passphrase.
+    pub async fn unlock_secrets(&mut self, passphrase: &[u8]) -> Result<()> {
+        self.unlocked_secrets = Some(UnlockedSecrets {
+            secrets: Self::decrypt_secrets(&self.config, passphrase).await?,
+            passphrase: Zeroizing::new(passphrase.to_vec()),
+        });
+
+        Ok(())
+    }
+
+    /// The decrypted secrets, or `None` if they have not been unlocked with a passphrase.
+    pub fn secrets(&self) -> Option<&Secrets> {
+        self.unlocked_secrets
+            .as_ref()
+            .map(|unlocked_secrets| &unlocked_secrets.secrets)
+    }
+
+    /// Like [`Self::secrets`], but for modifying the secrets, which are stored in the registry
+    /// config when it is saved.
+    pub fn secrets_mut(&mut self) -> Option<&mut Secrets> {
+        self.unlocked_secrets
+            .as_mut()
+            .map(|unlocked_secrets| &mut unlocked_secrets.secrets)
+    }
+
+    /// Returns no secrets if none are stored.
+    async fn decrypt_secrets(config: &OwnedRegistryConfig, passphrase: &[u8]) -> Result<Secrets> {
+        match config.secrets.as_ref() {
+            Some(secrets) => secrets.decrypt(passphrase).await,
+            None => Ok(Secrets::new()),
+        }
     }
 
     pub async fn save_config(&mut self) -> Result<()> {
+        if let Some(unlocked_secrets) = self.unlocked_secrets.as_ref() {
+            self.config.secrets = if unlocked_secrets.secrets.is_empty() {
+                None
+            } else {
+                Some(
+                    EncryptedSecrets::encrypt(
+                        &unlocked_secrets.secrets,
+                        &unlocked_secrets.passphrase,
+                    )
+                    .await?,
+                )
+            };
+        }
+
         let config_string = toml::to_string_pretty(&self.config)?;
 
         self.file_lock.file_mut().seek(SeekFrom::Start(0)).await?;
@@ -127,6 +494,11 @@ impl<L: FileLock> OwnedRegistry<L> {
             .file_mut()
             .write_all(config_string.as_bytes())
             .await?;
+        // Remove the rest of a previous, longer config.
+        self.file_lock
+            .file_mut()
+            .set_len(config_string.len() as u64)
+            .await?;
 
         Ok(())
     }
@@ -150,6 +522,14 @@ impl<L: FileLock> OwnedRegistry<L> {
         Self::get_key_path_from_record_directory_path(&self.directory_path, key_path)
     }
 
+    /// Paths of the files at `config.signing_key_paths`, in the same order.
+    pub fn get_signing_key_paths(&self) -> Vec<PathBuf> {
+        self.signing_key_paths
+            .iter()
+            .map(|key_path| self.get_key_path(key_path))
+            .collect()
+    }
+
     pub fn get_staging_directory_path(&self) -> PathBuf {
         self.directory_path.join(&self.staging_directory_path)
     }
@@ -162,12 +542,303 @@ impl<L: FileLock> OwnedRegistry<L> {
         self.directory_path.join(&self.published_directory_path)
     }
 
-    fn get_root_record_path(&self) -> PathBuf {
+    pub fn get_root_record_path(&self) -> PathBuf {
         self.directory_path.join(&self.root_record_path)
     }
 
+    /// Ensures that none of the output directories are equal to, ancestors of, or inside the root
+    /// record directory, so that a build never reads its own output as source.
+    pub fn ensure_output_disjoint_from_source(&self) -> Result<()> {
+        let root_record_directory = util::fs::normalize_path(self.get_root_record_path())?;
+
+        for output_directory in [
+            self.get_staging_directory_path(),
+            self.get_revisions_directory_path(),
+            self.get_published_directory_path(),
+        ] {
+            let output_directory = util::fs::normalize_path(output_directory)?;
+
+            if output_directory.starts_with(&root_record_directory)
+                || root_record_directory.starts_with(&output_directory)
+            {
+                return Err(Error::OutputOverlapsSource {
+                    output_directory,
+                    root_record_directory,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the output registry in the staging directory, which `make` writes the records into.
+    pub async fn create_output_registry(&self, overwrite: bool) -> Result<Registry<WriteLock>> {
+        self.create_output_registry_with_config(RegistryConfig::from(self), overwrite)
+            .await
+    }
+
+    /// Like [`Self::create_output_registry`], but with a separately provided output registry config,
+    /// which must be compatible with this registry.
+    pub async fn create_output_registry_with_config(
+        &self,
+        output_registry_config: RegistryConfig,
+        overwrite: bool,
+    ) -> Result<Registry<WriteLock>> {
+        self.create_output_registry_at(
+            self.get_staging_directory_path(),
+            output_registry_config,
+            overwrite,
+        )
+        .await
+    }
+
+    /// Like [`Self::create_output_registry_with_config`], but in `directory_path` rather than in the
+    /// staging directory.
+    pub async fn create_output_registry_at(
+        &self,
+        directory_path: impl AsRef<Path>,
+        output_registry_config: RegistryConfig,
+        overwrite: bool,
+    ) -> Result<Registry<WriteLock>> {
+        self.validate_output_registry_config(&output_registry_config)?;
+
+        Ok(Registry::create(
+            directory_path.as_ref().to_owned(),
+            output_registry_config,
+            overwrite,
+        )
+        .await?)
+    }
+
+    /// Prepares the pending staging directory, which an atomic build writes to instead of the
+    /// staging directory, as a copy of the current staging directory.
+    /// Returns the path to the pending staging directory.
+    pub async fn prepare_pending_staging(&self) -> Result<PathBuf> {
+        let pending_directory_path = self.get_pending_staging_directory_path();
+
+        // Remove leftovers of an interrupted build.
+        util::fs::remove_directory_if_exists(&pending_directory_path).await?;
+        self.copy_staging_to(&pending_directory_path).await?;
+
+        Ok(pending_directory_path)
+    }
+
+    /// Copies the staging directory to `directory_path`, if it exists, e.g. so that a build can be
+    /// compared against it without modifying it.
+    pub async fn copy_staging_to(&self, directory_path: impl AsRef<Path>) -> Result<()> {
+        let staging_directory_path = self.get_staging_directory_path();
+
+        if tokio::fs::try_exists(&staging_directory_path).await? {
+            util::fs::copy_directory(&staging_directory_path, directory_path).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the staging directory with the pending staging directory, after a successful build.
+    pub async fn commit_pending_staging(&self) -> Result<()> {
+        let staging_directory_path = self.get_staging_directory_path();
+        let previous_directory_path = self.get_previous_staging_directory_path();
+
+        util::fs::remove_directory_if_exists(&previous_directory_path).await?;
+
+        if tokio::fs::try_exists(&staging_directory_path).await? {
+            tokio::fs::rename(&staging_directory_path, &previous_directory_path).await?;
+        }
+
+        tokio::fs::rename(
+            self.get_pending_staging_directory_path(),
+            &staging_directory_path,
+        )
+        .await?;
+        util::fs::remove_directory_if_exists(&previous_directory_path).await?;
+
+        Ok(())
+    }
+
+    /// Replaces the contents of `directory_path` with a copy of the staging directory, so that the
+    /// same build can be deployed to several locations.
+    pub async fn replicate_staging(&self, directory_path: impl AsRef<Path>) -> Result<()> {
+        self.ensure_extra_staging_disjoint(&directory_path)?;
+        util::fs::remove_directory_if_exists(&directory_path).await?;
+        util::fs::copy_directory(self.get_staging_directory_path(), &directory_path).await
+    }
+
+    /// Ensures that replacing `directory_path` with [`Self::replicate_staging`] removes neither the
+    /// registry directory, nor the root record directory, nor any of the output directories.
+    pub fn ensure_extra_staging_disjoint(&self, directory_path: impl AsRef<Path>) -> Result<()> {
+        let directory_path = util::fs::normalize_path(directory_path)?;
+        let registry_directory_path = util::fs::normalize_path(&self.directory_path)?;
+
+        if registry_directory_path.starts_with(&directory_path) {
+            return Err(Error::ExtraStagingOverlaps {
+                directory: directory_path,
+                other: registry_directory_path,
+            }
+            .into());
+        }
+
+        for other_directory_path in [
+            self.get_root_record_path(),
+            self.get_staging_directory_path(),
+            self.get_revisions_directory_path(),
+            self.get_published_directory_path(),
+        ] {
+            let other_directory_path = util::fs::normalize_path(other_directory_path)?;
+
+            if directory_path.starts_with(&other_directory_path)
+                || other_directory_path.starts_with(&directory_path)
+            {
+                return Err(Error::ExtraStagingOverlaps {
+                    directory: directory_path,
+                    other: other_directory_path,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `directory_path` can be replaced by [`Self::replicate_staging`] without losing
+    /// anything but a previous build, as it does not exist, is empty, or contains an output registry.
+    pub async fn is_replaceable_extra_staging(directory_path: impl AsRef<Path>) -> Result<bool> {
+        let mut entries = match tokio::fs::read_dir(&directory_path).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+            Err(error) => return Err(error.into()),
+        };
+
+        if entries.next_entry().await?.is_none() {
+            return Ok(true);
+        }
+
+        // Output registries store their config under the same file name.
+        Ok(
+            tokio::fs::try_exists(Self::get_config_path_from_registry_directory_path(
+                &directory_path,
+            ))
+            .await?,
+        )
+    }
+
+    /// Removes the pending staging directory after a failed build, leaving the staging directory
+    /// untouched.
+    pub async fn discard_pending_staging(&self) -> Result<()> {
+        util::fs::remove_directory_if_exists(self.get_pending_staging_directory_path()).await
+    }
+
+    /// Restores the staging directory after [`Self::commit_pending_staging`] was interrupted, which
+    /// would otherwise leave only the previous staging directory behind.
+    pub async fn recover_staging(&self) -> Result<()> {
+        let staging_directory_path = self.get_staging_directory_path();
+        let previous_directory_path = self.get_previous_staging_directory_path();
+
+        if !tokio::fs::try_exists(&previous_directory_path).await? {
+            return Ok(());
+        }
+
+        if tokio::fs::try_exists(&staging_directory_path).await? {
+            // Interrupted after the pending staging directory was moved into place.
+            util::fs::remove_directory_if_exists(&previous_directory_path).await
+        } else {
+            warn!(
+                ?staging_directory_path,
+                "Restoring the staging directory of the last build, as replacing it was interrupted."
+            );
+            tokio::fs::rename(&previous_directory_path, &staging_directory_path).await?;
+
+            Ok(())
+        }
+    }
+
+    /// The sibling of the staging directory which atomic builds are written to.
+    pub fn get_pending_staging_directory_path(&self) -> PathBuf {
+        Self::with_file_name_suffix(&self.get_staging_directory_path(), ".pending")
+    }
+
+    /// The sibling of the staging directory which it is moved to while being replaced.
+    fn get_previous_staging_directory_path(&self) -> PathBuf {
+        Self::with_file_name_suffix(&self.get_staging_directory_path(), ".previous")
+    }
+
+    fn with_file_name_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_owned();
+
+        file_name.push(suffix);
+        path.with_file_name(file_name)
+    }
+
+    /// Ensures records made from this registry are readable in a registry with the provided config.
+    /// That requires the same hashing and key derivation, and the verifying keys of this registry's
+    /// signing keys. Additional verifying keys are allowed, as they do not prevent the records from
+    /// being verified.
+    pub fn validate_output_registry_config(
+        &self,
+        output_registry_config: &RegistryConfig,
+    ) -> Result<()> {
+        let expected_config = RegistryConfig::from(self);
+        let mismatched_field = if output_registry_config.hash != expected_config.hash {
+            Some("hash")
+        } else if output_registry_config.kdf != expected_config.kdf {
+            Some("kdf")
+        } else if !expected_config.verifying_keys.iter().all(|verifying_key| {
+            output_registry_config
+                .verifying_keys
+                .contains(verifying_key)
+        }) {
+            Some("verifying_keys")
+        } else {
+            None
+        };
+
+        if let Some(field) = mismatched_field {
+            return Err(Error::OutputRegistryConfigMismatch { field }.into());
+        }
+
+        Ok(())
+    }
+
+    /// Opens the output registry in the staging directory for reading, e.g. after it has been built.
+    pub async fn open_output_registry(&self) -> Result<Registry<ReadLock>> {
+        Ok(Registry::open(self.get_staging_directory_path()).await?)
+    }
+
     pub async fn load_root_record(&self) -> Result<OwnedRecord> {
-        OwnedRecord::load_from_directory(&self.config, None, self.get_root_record_path()).await
+        self.load_root_record_with_options(&Default::default())
+            .await
+    }
+
+    pub async fn load_root_record_with_options(
+        &self,
+        options: &OwnedRecordLoadOptions,
+    ) -> Result<OwnedRecord> {
+        self.load_root_record_from(self.get_root_record_path(), options)
+            .await
+    }
+
+    /// Like [`Self::load_root_record_with_options`], but loads the root record from
+    /// `directory_path` instead of the root record directory of the registry.
+    pub async fn load_root_record_from(
+        &self,
+        directory_path: impl AsRef<Path> + Send + Sync,
+        options: &OwnedRecordLoadOptions,
+    ) -> Result<OwnedRecord> {
+        let mut root_record = OwnedRecord::load_from_directory(
+            &self.directory_path,
+            &self.config,
+            None,
+            directory_path,
+            options,
+        )
+        .await?;
+
+        if let Some(root_record_name) = self.root_record_name.as_ref() {
+            root_record.config.name = root_record_name.clone();
+        }
+
+        Ok(root_record)
     }
 }
 
@@ -189,11 +860,53 @@ impl OwnedRegistry<ReadLock> {
             directory_path: self.directory_path,
             config: self.config,
             signing_keys: self.signing_keys,
+            unlocked_secrets: self.unlocked_secrets,
+            deny_unknown_config_keys: self.deny_unknown_config_keys,
         })
     }
 }
 
 impl OwnedRegistry<WriteLock> {
+    /// Replaces the root predecessor nonce with a freshly generated one, preserving the formatting
+    /// of the config file.
+    /// This changes the hashed keys of all records, so the whole registry has to be rebuilt, and
+    /// the previously published registry can no longer be updated.
+    pub async fn rotate_root_predecessor_nonce(&mut self) -> Result<()> {
+        let mut config_string = String::new();
+
+        self.file_lock.file_mut().seek(SeekFrom::Start(0)).await?;
+        self.file_lock
+            .file_mut()
+            .read_to_string(&mut config_string)
+            .await?;
+
+        let mut config_doc = config_string.parse::<DocumentMut>()?;
+        let root_predecessor_nonce =
+            RegistryConfigKdf::generate_random_root_predecessor_nonce(OsRng, None);
+        let root_predecessor_nonce_string =
+            format!("{:02x}", root_predecessor_nonce.iter().format(""));
+        config_doc["kdf"]["root_predecessor_nonce"] =
+            toml_edit::value(root_predecessor_nonce_string);
+
+        let config_string = config_doc.to_string();
+
+        // Validated before writing, so that an invalid config never replaces a valid one.
+        toml::from_str::<OwnedRegistryConfig>(&config_string)?.validate(&self.get_config_path())?;
+
+        // Written through the locked file, so that the lock is held throughout.
+        self.file_lock.file_mut().seek(SeekFrom::Start(0)).await?;
+        self.file_lock
+            .file_mut()
+            .write_all(config_string.as_bytes())
+            .await?;
+        self.file_lock
+            .file_mut()
+            .set_len(config_string.len() as u64)
+            .await?;
+        self.file_lock.file_mut().sync_all().await?;
+        self.reload().await
+    }
+
     /// Creates a new registry with generated cryptographic keys, and the provided root record.
     /// The root record is signed but **not encrypted**, it is the record displayed to the user
     /// upon opening the registry.
@@ -302,7 +1015,14 @@ impl OwnedRegistry<WriteLock> {
                 .join(format!("key_{}.pem", signing_key.key_type_name()));
             let signing_key_path_absolute = directory_path.join(&signing_key_path_relative);
             let pem = signing_key.to_pkcs8_pem(LineEnding::default()).unwrap();
-            let mut file = File::create_new(&signing_key_path_absolute).await?;
+            let mut file = {
+                let mut open_options = OpenOptions::new();
+                open_options.write(true).create_new(true);
+                // The key must only be readable by its owner.
+                #[cfg(unix)]
+                open_options.mode(0o600);
+                open_options.open(&signing_key_path_absolute).await?
+            };
 
             file.write_all(pem.as_bytes()).await?;
 
@@ -313,12 +1033,33 @@ impl OwnedRegistry<WriteLock> {
             directory_path,
             config,
             signing_keys,
+            unlocked_secrets: None,
+            deny_unknown_config_keys: false,
             file_lock,
         };
 
         Ok(registry)
     }
 
+    /// Generates a new source directory, like [`OwnedRegistry::generate`], but with the records
+    /// described by `root_record_spec` in place of the template records.
+    ///
+    /// Only the records are taken from the spec, the registry config is the generated one. The
+    /// data of each record is written as `data.txt`, so it must be UTF-8; records with binary
+    /// data have to be added to the source directory afterwards.
+    pub async fn generate_from_spec(
+        directory_path: impl Into<PathBuf>,
+        root_record_spec: &RecordSpec,
+    ) -> Result<Self> {
+        let registry = Self::generate(directory_path, false).await?;
+        let root_record_path = registry.directory_path.join(&registry.root_record_path);
+
+        tokio::fs::remove_dir_all(&root_record_path).await?;
+        root_record_spec.materialize(&root_record_path).await?;
+
+        Ok(registry)
+    }
+
     pub async fn lock_read(self) -> Result<OwnedRegistry<ReadLock>> {
         let config_path = self.get_config_path();
         let open_options = {
@@ -334,6 +1075,8 @@ impl OwnedRegistry<WriteLock> {
             directory_path: self.directory_path,
             config: self.config,
             signing_keys: self.signing_keys,
+            unlocked_secrets: self.unlocked_secrets,
+            deny_unknown_config_keys: self.deny_unknown_config_keys,
         })
     }
 }
@@ -358,12 +1101,16 @@ impl<L: FileLock> PartialEq for OwnedRegistry<L> {
             directory_path: self_directory_path,
             config: self_config,
             signing_keys: self_signing_keys,
+            unlocked_secrets: _,
+            deny_unknown_config_keys: _,
             file_lock: _,
         } = self;
         let Self {
             directory_path: other_directory_path,
             config: other_config,
             signing_keys: other_signing_keys,
+            unlocked_secrets: _,
+            deny_unknown_config_keys: _,
             file_lock: _,
         } = other;
         self_directory_path == other_directory_path
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use color_eyre::Result;
+use rrr::crypto::{
+    encryption::EncryptionAlgorithm,
+    password_hash::{argon2::Argon2Params, PasswordHashAlgorithm},
+};
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+
+/// Decrypted secrets by their names. The values are zeroed when dropped.
+pub type Secrets = BTreeMap<String, Zeroizing<String>>;
+
+/// Named secrets stored in the registry config, encrypted at rest with a key derived from a master
+/// passphrase of the registry.
+///
+/// The key is derived with the same password hashing algorithm as record keys, and the secrets
+/// are encrypted with the same encryption algorithm as records.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EncryptedSecrets {
+    pub password_hash: PasswordHashAlgorithm,
+    pub encryption_algorithm: EncryptionAlgorithm,
+    pub salt: ByteBuf,
+    pub nonce: ByteBuf,
+    pub ciphertext: ByteBuf,
+}
+
+impl EncryptedSecrets {
+    const SALT_LENGTH_IN_BYTES: usize = 16;
+
+    /// Encrypts `secrets` with a key derived from `passphrase`.
+    pub async fn encrypt(secrets: &Secrets, passphrase: &[u8]) -> Result<Self> {
+        let password_hash = PasswordHashAlgorithm::Argon2(Argon2Params::default());
+        let encryption_algorithm = EncryptionAlgorithm::Aes256Gcm;
+        let mut salt = vec![0; Self::SALT_LENGTH_IN_BYTES];
+        let mut nonce = vec![0; encryption_algorithm.nonce_length_in_bytes()];
+
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = Self::derive_key(&password_hash, encryption_algorithm, passphrase, &salt).await?;
+        let plaintext = Zeroizing::new(toml::to_string(
+            &secrets
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect::<BTreeMap<_, _>>(),
+        )?);
+        let ciphertext = encryption_algorithm.encrypt(&key, &nonce, plaintext.as_bytes())?;
+
+        Ok(Self {
+            password_hash,
+            encryption_algorithm,
+            salt: ByteBuf::from(salt),
+            nonce: ByteBuf::from(nonce),
+            ciphertext: ByteBuf::from(ciphertext),
+        })
+    }
+
+    /// Decrypts the secrets with a key derived from `passphrase`.
+    pub async fn decrypt(&self, passphrase: &[u8]) -> Result<Secrets> {
+        let key = Self::derive_key(
+            &self.password_hash,
+            self.encryption_algorithm,
+            passphrase,
+            &self.salt,
+        )
+        .await?;
+        let plaintext = Zeroizing::new(
+            self.encryption_algorithm
+                .decrypt(&key, &self.nonce, &self.ciphertext)
+                .map_err(|_| Error::SecretsDecryptionFailed)?,
+        );
+        let secrets = toml::from_str::<BTreeMap<String, String>>(std::str::from_utf8(&plaintext)?)?;
+
+        Ok(secrets
+            .into_iter()
+            .map(|(name, value)| (name, Zeroizing::new(value)))
+            .collect())
+    }
+
+    async fn derive_key(
+        password_hash: &PasswordHashAlgorithm,
+        encryption_algorithm: EncryptionAlgorithm,
+        passphrase: &[u8],
+        salt: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let mut key = Zeroizing::new(vec![0; encryption_algorithm.key_length_in_bytes()]);
+
+        password_hash
+            .hash_password(passphrase, salt, &mut key)
+            .await?;
+
+        Ok(key)
+    }
+}
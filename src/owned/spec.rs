@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+use crate::record::{
+    OwnedRecord, OwnedRecordConfigParametersUnresolved, OwnedRecordConfigUnresolved,
+    OwnedRecordMetadata,
+};
+
+/// Describes a tree of records along with their inline data, as an alternative to a source
+/// directory, e.g. for registries generated by other programs. Specs only describe records; the
+/// registry config always comes from the source directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecordSpec {
+    pub name: String,
+    /// The UTF-8 data of the record, if any, which is written as `data.txt`. Binary data is not
+    /// supported.
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(flatten)]
+    pub parameters: OwnedRecordConfigParametersUnresolved,
+    #[serde(default)]
+    pub successive_records: Vec<RecordSpec>,
+}
+
+impl RecordSpec {
+    /// Writes the record and its successive records into `directory_path`, in the layout of a
+    /// source directory. Successive records are stored in subdirectories named after their index.
+    pub async fn materialize(&self, directory_path: impl AsRef<Path>) -> Result<()> {
+        let mut specs = vec![(self, directory_path.as_ref().to_owned())];
+
+        while let Some((spec, directory_path)) = specs.pop() {
+            let config = OwnedRecordConfigUnresolved {
+                name: ByteBuf::from(spec.name.as_bytes()),
+                data_ref: None,
+                intermediate: None,
+                draft: false,
+                text: false,
+                version_ignore_metadata: Vec::new(),
+                metadata: OwnedRecordMetadata { created_at: None },
+                parameters: spec.parameters.clone(),
+            };
+
+            tokio::fs::create_dir_all(&directory_path).await?;
+            tokio::fs::write(
+                OwnedRecord::get_config_path_from_record_directory_path(&directory_path),
+                toml::to_string_pretty(&config)?,
+            )
+            .await?;
+
+            if let Some(data) = spec.data.as_ref() {
+                tokio::fs::write(directory_path.join("data.txt"), data).await?;
+            }
+
+            for (index, successive_spec) in spec.successive_records.iter().enumerate() {
+                let successive_directory_path: PathBuf = directory_path.join(index.to_string());
+
+                specs.push((successive_spec, successive_directory_path));
+            }
+        }
+
+        Ok(())
+    }
+}
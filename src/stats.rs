@@ -0,0 +1,75 @@
+use std::{collections::BTreeMap, path::Path};
+
+use color_eyre::Result;
+use rrr::utils::fd_lock::FileLock;
+use serde::Serialize;
+
+use crate::{record::OwnedRecord, registry::OwnedRegistry};
+
+/// Describes the scale of a registry, both of its source directory and of its output registry.
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct RegistryStatistics {
+    pub source_records: usize,
+    /// The number of source records encrypted with each encryption algorithm, with unencrypted
+    /// records counted under `"none"`.
+    pub source_records_by_encryption: BTreeMap<String, usize>,
+    /// The total size of the data files of all source records.
+    pub source_data_bytes: u64,
+    /// The total size of all files in the staging directory.
+    pub output_registry_bytes: u64,
+}
+
+impl RegistryStatistics {
+    pub async fn collect<L: FileLock>(
+        registry: &OwnedRegistry<L>,
+        root_record: &OwnedRecord,
+    ) -> Result<Self> {
+        let mut stats = Self::default();
+        for (_, record) in root_record.iter() {
+            let encryption_name = match record.config.parameters.encryption.as_ref() {
+                Some(encryption) => format!("{:?}", encryption.algorithm),
+                None => "none".to_owned(),
+            };
+
+            stats.source_records += 1;
+            *stats
+                .source_records_by_encryption
+                .entry(encryption_name)
+                .or_default() += 1;
+
+            for data_path in record.get_data_paths().await? {
+                stats.source_data_bytes += tokio::fs::metadata(data_path).await?.len();
+            }
+        }
+
+        stats.output_registry_bytes = directory_size(registry.get_staging_directory_path()).await?;
+
+        Ok(stats)
+    }
+}
+
+/// Computes the total size of all files in the directory, or zero if it does not exist.
+async fn directory_size(directory_path: impl AsRef<Path>) -> Result<u64> {
+    let mut size = 0;
+    let mut directory_paths = vec![directory_path.as_ref().to_owned()];
+
+    while let Some(directory_path) = directory_paths.pop() {
+        let mut read_dir = match tokio::fs::read_dir(&directory_path).await {
+            Ok(read_dir) => read_dir,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error.into()),
+        };
+
+        while let Some(entry) = read_dir.next_entry().await? {
+            let file_type = entry.file_type().await?;
+
+            if file_type.is_dir() {
+                directory_paths.push(entry.path());
+            } else if file_type.is_file() {
+                size += entry.metadata().await?.len();
+            }
+        }
+    }
+
+    Ok(size)
+}
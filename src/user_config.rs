@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::record::OwnedRecordConfigParametersUnresolved;
+
+/// Settings shared by all registries of a user, stored in `rrr-make/config.toml` within the user's
+/// config directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UserConfig {
+    /// Used for those record parameters which neither a record config nor the registry config
+    /// specifies.
+    #[serde(default)]
+    pub default_record_parameters: OwnedRecordConfigParametersUnresolved,
+}
+
+impl UserConfig {
+    /// The path to the user config, within `$XDG_CONFIG_HOME`, or `$HOME/.config` if unset.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_directory_path = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(config_directory_path.join("rrr-make/config.toml"))
+    }
+
+    /// Loads the user config at `path`, or the default user config if the file does not exist.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(config_string) => Ok(toml::from_str(&config_string)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::{Component, Path, PathBuf};
+
+use color_eyre::Result;
+
+/// Recursively copies the contents of the `source` directory into the `target` directory, creating
+/// it if necessary. Symbolic links are not followed, but skipped.
+pub async fn copy_directory(source: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<()> {
+    let mut directories = vec![(source.as_ref().to_owned(), target.as_ref().to_owned())];
+
+    while let Some((source, target)) = directories.pop() {
+        tokio::fs::create_dir_all(&target).await?;
+
+        let mut read_dir = tokio::fs::read_dir(&source).await?;
+
+        while let Some(entry) = read_dir.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let entry_target: PathBuf = target.join(entry.file_name());
+
+            if file_type.is_dir() {
+                directories.push((entry.path(), entry_target));
+            } else if file_type.is_file() {
+                tokio::fs::copy(entry.path(), entry_target).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the directory and all of its contents, if it exists.
+pub async fn remove_directory_if_exists(path: impl AsRef<Path>) -> Result<()> {
+    match tokio::fs::remove_dir_all(path).await {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Makes the path absolute and lexically resolves its `.` and `..` components, without accessing
+/// the file system, so that paths which do not exist yet can be compared.
+pub fn normalize_path(path: impl AsRef<Path>) -> Result<PathBuf> {
+    let mut normalized_path = PathBuf::new();
+
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized_path.pop();
+            }
+            component => normalized_path.push(component),
+        }
+    }
+
+    Ok(normalized_path)
+}
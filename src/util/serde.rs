@@ -1,8 +1,10 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, path::PathBuf};
 
-use ::serde::{Deserialize, Serialize};
+use ::serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use serde::de::{Unexpected, Visitor};
 
+use crate::error::Error;
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum ExplicitOption<T> {
@@ -93,6 +95,21 @@ impl<'de> Deserialize<'de> for ExplicitNone {
                     ))
                 }
             }
+
+            /// Accepts `null` in self-describing formats other than TOML.
+            fn visit_unit<E>(self) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(ExplicitNone::new())
+            }
+
+            fn visit_none<E>(self) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(ExplicitNone::new())
+            }
         }
 
         deserializer.deserialize_any(ExplicitNoneVisitor)
@@ -108,11 +125,51 @@ impl ExplicitNone {
 }
 
 /// Used to disambiguate between an unspecified field and a `null` field.
+/// Fields of this type should be annotated with
+/// `#[serde(default, deserialize_with = "deserialize_double_option", skip_serializing_if = "Option::is_none")]`,
+/// so that `null` is not mistaken for an unspecified field in formats which support it.
 pub type DoubleOption<T> = Option<ExplicitOption<T>>;
 
+/// Deserializes a present [`DoubleOption`] field, including `null`, as `Some`.
+/// An unspecified field is handled by `#[serde(default)]`.
+pub fn deserialize_double_option<'de, D, T>(deserializer: D) -> Result<DoubleOption<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    ExplicitOption::deserialize(deserializer).map(Some)
+}
+
+/// Deserializes a `T` from `value`, and returns the keys which `T` ignored, in the form
+/// `parent.key`. Keys within values deserialized from a buffer, such as flattened fields and
+/// untagged enums, are not reported.
+pub fn find_ignored_keys<T: DeserializeOwned>(
+    value: toml::Value,
+) -> Result<Vec<String>, toml::de::Error> {
+    let mut ignored_keys = Vec::new();
+    let _: T = serde_ignored::deserialize(value, |path| ignored_keys.push(path.to_string()))?;
+
+    Ok(ignored_keys)
+}
+
+/// Fails with [`Error::UnknownConfigKeys`] if any `unknown_keys` of the config in `path` were found.
+pub(crate) fn ensure_no_unknown_keys(
+    path: impl Into<PathBuf>,
+    unknown_keys: Vec<String>,
+) -> Result<(), Error> {
+    if unknown_keys.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::UnknownConfigKeys {
+            path: path.into(),
+            keys: unknown_keys,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::util::serde::DoubleOption;
+    use crate::util::serde::{deserialize_double_option, DoubleOption};
 
     use super::ExplicitOption;
     use serde::{Deserialize, Serialize};
@@ -238,4 +295,65 @@ mod tests {
             None,
         );
     }
+
+    #[test]
+    fn explicit_option_null() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+        struct RootDouble {
+            #[serde(
+                default,
+                deserialize_with = "deserialize_double_option",
+                skip_serializing_if = "Option::is_none"
+            )]
+            optional_field: DoubleOption<u64>,
+        }
+
+        fn from_json(json: &str) -> DoubleOption<u64> {
+            serde_json::from_str::<RootDouble>(json)
+                .unwrap()
+                .optional_field
+        }
+
+        fn from_toml(toml: &str) -> DoubleOption<u64> {
+            toml::from_str::<RootDouble>(toml).unwrap().optional_field
+        }
+
+        assert_eq!(
+            serde_json::from_str::<ExplicitOption<u64>>("null").unwrap(),
+            ExplicitOption::default()
+        );
+        assert_eq!(from_json("{}"), None);
+        assert_eq!(
+            from_json(r#"{"optional_field": null}"#),
+            Some(ExplicitOption::default())
+        );
+        assert_eq!(
+            from_json(r#"{"optional_field": "none"}"#),
+            Some(ExplicitOption::default())
+        );
+        assert_eq!(
+            from_json(r#"{"optional_field": 1}"#),
+            Some(ExplicitOption::Some(1))
+        );
+        assert_eq!(from_toml(""), None);
+        assert_eq!(
+            from_toml("optional_field = \"none\""),
+            Some(ExplicitOption::default())
+        );
+        assert_eq!(
+            from_toml("optional_field = 1"),
+            Some(ExplicitOption::Some(1))
+        );
+
+        for optional_field in [
+            None,
+            Some(ExplicitOption::default()),
+            Some(ExplicitOption::Some(1)),
+        ] {
+            let original = RootDouble { optional_field };
+            let json = serde_json::to_string(&original).unwrap();
+
+            assert_eq!(serde_json::from_str::<RootDouble>(&json).unwrap(), original);
+        }
+    }
 }
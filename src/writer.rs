@@ -0,0 +1,108 @@
+use color_eyre::eyre::{OptionExt, Result};
+use futures::{future::BoxFuture, FutureExt};
+use rrr::{
+    crypto::signature::SigningKey,
+    record::{
+        segment::{RecordVersion, SegmentEncryption},
+        HashedRecordKey, Record,
+    },
+    registry::Registry,
+    utils::fd_lock::WriteLock,
+};
+
+/// The target that built records are written to by [`crate::make_recursive`].
+///
+/// The output registry on the local filesystem is the default implementation. Other backends,
+/// such as object stores, can be supported by implementing this trait. An implementation is
+/// responsible for storing each version of a record such that it can later be read back by
+/// [`RegistryWriter::read_latest_record_version`], which is used to decide whether a record has
+/// changed since the previous build.
+pub trait RegistryWriter: Send {
+    /// Reads the latest version of the record stored under `hashed_key`, along with its version,
+    /// or returns `None` if no version of the record has been written yet.
+    fn read_latest_record_version<'a>(
+        &'a mut self,
+        hashed_key: &'a HashedRecordKey,
+        max_version_lookahead: u64,
+        max_collision_resolution_attempts: u64,
+    ) -> BoxFuture<'a, Result<Option<(RecordVersion, Record)>>>;
+
+    /// Writes `record` as the given `version` of the record stored under `hashed_key`, split into
+    /// segments according to `split_at` and signed with `signing_keys`.
+    #[allow(clippy::too_many_arguments)]
+    fn save_record<'a>(
+        &'a mut self,
+        signing_keys: &'a [SigningKey],
+        hashed_key: &'a HashedRecordKey,
+        record: &'a Record,
+        version: RecordVersion,
+        max_collision_resolution_attempts: u64,
+        split_at: &'a [usize],
+        encryption: Option<&'a SegmentEncryption>,
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+impl RegistryWriter for Registry<WriteLock> {
+    fn read_latest_record_version<'a>(
+        &'a mut self,
+        hashed_key: &'a HashedRecordKey,
+        max_version_lookahead: u64,
+        max_collision_resolution_attempts: u64,
+    ) -> BoxFuture<'a, Result<Option<(RecordVersion, Record)>>> {
+        async move {
+            let existing_versions = self
+                .list_record_versions(
+                    hashed_key,
+                    max_version_lookahead,
+                    max_collision_resolution_attempts,
+                )
+                .await?;
+            let Some(latest_existing_version) = existing_versions.last() else {
+                return Ok(None);
+            };
+            let latest_existing_version_record = Record::read_version_with_nonce(
+                self,
+                hashed_key,
+                latest_existing_version.record_version,
+                latest_existing_version.record_nonce,
+            )
+            .await?
+            .ok_or_eyre("Failed to load the latest version of a record.")?;
+
+            Ok(Some((
+                latest_existing_version.record_version,
+                latest_existing_version_record.record,
+            )))
+        }
+        .boxed()
+    }
+
+    fn save_record<'a>(
+        &'a mut self,
+        signing_keys: &'a [SigningKey],
+        hashed_key: &'a HashedRecordKey,
+        record: &'a Record,
+        version: RecordVersion,
+        max_collision_resolution_attempts: u64,
+        split_at: &'a [usize],
+        encryption: Option<&'a SegmentEncryption>,
+    ) -> BoxFuture<'a, Result<()>> {
+        async move {
+            Registry::save_record(
+                self,
+                signing_keys,
+                hashed_key,
+                record,
+                version,
+                max_collision_resolution_attempts,
+                split_at,
+                encryption,
+                false,
+            )
+            .await?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+}